@@ -0,0 +1,115 @@
+//! Compares the boxed AST with the arena AST:
+//!
+//! ```text
+//! cargo bench
+//! ```
+//!
+//! Allocations are counted by a global allocator, which is why this is a
+//! target of its own without the test harness: nothing else runs under the
+//! allocator, and nothing runs alongside the measurement.
+
+use invariant::Lexer;
+use invariant::ast::BoxBuilder;
+use invariant::ast::arena::ArenaBuilder;
+use invariant::parser::{Parser, TreeBuilder};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(live, Ordering::Relaxed);
+
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// What one parse cost, measured while the tree was alive.
+struct Measurement {
+    time: Duration,
+    allocations: usize,
+    retained: usize,
+    peak: usize,
+}
+
+fn measure<B: TreeBuilder>(source: &str, builder: impl Fn() -> B, runs: u32) -> Measurement {
+    let start = Instant::now();
+    for _ in 0..runs {
+        black_box(Parser::with_builder(Lexer::new(source), builder()).parse());
+    }
+    let time = start.elapsed() / runs;
+
+    let before = LIVE.load(Ordering::Relaxed);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+
+    let (program, errors) = Parser::with_builder(Lexer::new(source), builder()).parse();
+    assert!(errors.is_empty());
+
+    let measurement = Measurement {
+        time,
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        retained: LIVE.load(Ordering::Relaxed) - before,
+        peak: PEAK.load(Ordering::Relaxed) - before,
+    };
+    drop(black_box(program));
+
+    measurement
+}
+
+/// A program of `functions` functions that uses every kind of node.
+fn program(functions: usize) -> String {
+    let mut source = String::new();
+    for index in 0..functions {
+        let _ = write!(
+            source,
+            "fn function{index}(a, b, c) {{\n\
+             \x20   let total = a * 2 + b / -3;\n\
+             \x20   while total < c && !(total == 100) {{\n\
+             \x20       total = total + function{index}(a, b - 1, c);\n\
+             \x20   }}\n\
+             \x20   if total >= 10 || false {{ return total; }} else {{ return 0; }}\n\
+             }}\n\n"
+        );
+    }
+
+    source
+}
+
+fn main() {
+    let source = program(10_000);
+    let runs = 20;
+
+    println!("parsing {} KiB, {runs} runs", source.len() / 1024);
+    for (name, measurement) in [
+        ("boxed", measure(&source, BoxBuilder::default, runs)),
+        ("arena", measure(&source, ArenaBuilder::default, runs)),
+    ] {
+        println!(
+            "{name}: {:>8.2?} per parse, {:>8} allocations, {:>6} KiB retained, {:>6} KiB peak",
+            measurement.time,
+            measurement.allocations,
+            measurement.retained / 1024,
+            measurement.peak / 1024,
+        );
+    }
+}
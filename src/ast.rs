@@ -1,5 +1,16 @@
+pub mod arena;
+mod build;
+pub mod fold;
+pub mod id;
+mod printer;
+pub mod visit;
+
+pub use build::BoxBuilder;
+use id::NodeId;
+pub use printer::TreePrinter;
 use std::range::Range;
 
+#[derive(Debug)]
 pub struct Program {
     items: Vec<Item>,
 }
@@ -8,108 +19,188 @@ impl Program {
     pub fn new(items: Vec<Item>) -> Self {
         Self { items }
     }
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    pub fn items_mut(&mut self) -> &mut [Item] {
+        &mut self.items
+    }
+
+    pub fn into_items(self) -> Vec<Item> {
+        self.items
+    }
 }
 
+#[derive(Debug)]
 pub enum Item {
     Function {
         name: String,
         arguments: Vec<String>,
         body: Statement,
         span: Range<usize>,
+        id: NodeId,
     },
 }
 
+impl Item {
+    pub fn name(&self) -> &str {
+        match self {
+            Item::Function { name, .. } => name,
+        }
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Item::Function { span, .. } => *span,
+        }
+    }
+
+    pub fn id(&self) -> NodeId {
+        match self {
+            Item::Function { id, .. } => *id,
+        }
+    }
+}
+
+#[derive(Debug)]
 pub enum Statement {
     Block {
         body: Vec<Statement>,
         span: Range<usize>,
+        id: NodeId,
     },
     Binding {
         bind_to: String,
         value: Expression,
         span: Range<usize>,
+        id: NodeId,
     },
     Expression {
         expression: Expression,
         span: Range<usize>,
+        id: NodeId,
     },
     Return {
         value: Expression,
         span: Range<usize>,
+        id: NodeId,
     },
     Branch {
         condition: Expression,
         then: Box<Statement>,
         otherwise: Option<Box<Statement>>,
         span: Range<usize>,
+        id: NodeId,
     },
     While {
         condition: Expression,
         body: Box<Statement>,
         span: Range<usize>,
+        id: NodeId,
     },
+    /// Placeholder for a statement that failed to parse.
+    Error { span: Range<usize>, id: NodeId },
 }
 
 impl Statement {
     pub fn span(&self) -> Range<usize> {
         *match self {
-            Statement::Block { body, span } => span,
-            Statement::Binding {
-                bind_to,
-                value,
-                span,
-            } => span,
-            Statement::Expression { expression, span } => span,
-            Statement::Return { value, span } => span,
-            Statement::Branch {
-                condition,
-                then,
-                otherwise,
-                span,
-            } => span,
-            Statement::While {
-                condition,
-                body,
-                span,
-            } => span,
+            Statement::Block { span, .. } => span,
+            Statement::Binding { span, .. } => span,
+            Statement::Expression { span, .. } => span,
+            Statement::Return { span, .. } => span,
+            Statement::Branch { span, .. } => span,
+            Statement::While { span, .. } => span,
+            Statement::Error { span, .. } => span,
+        }
+    }
+
+    pub fn id(&self) -> NodeId {
+        *match self {
+            Statement::Block { id, .. } => id,
+            Statement::Binding { id, .. } => id,
+            Statement::Expression { id, .. } => id,
+            Statement::Return { id, .. } => id,
+            Statement::Branch { id, .. } => id,
+            Statement::While { id, .. } => id,
+            Statement::Error { id, .. } => id,
         }
     }
 }
 
+#[derive(Debug)]
 pub enum Expression {
     Integer {
         value: i64,
         span: Range<usize>,
+        id: NodeId,
     },
     Boolean {
         value: bool,
         span: Range<usize>,
+        id: NodeId,
     },
     Reference {
         name: String,
         span: Range<usize>,
+        id: NodeId,
     },
     Operation {
         operation: Operation,
         span: Range<usize>,
+        id: NodeId,
     },
     Assignment {
         assign_to: String,
         value: Box<Expression>,
         span: Range<usize>,
+        id: NodeId,
     },
     Call {
         name: String,
         arguments: Vec<Expression>,
         span: Range<usize>,
+        id: NodeId,
     },
+    /// Placeholder for an expression that failed to parse.
+    Error { span: Range<usize>, id: NodeId },
+}
+
+impl Expression {
+    pub fn span(&self) -> Range<usize> {
+        *match self {
+            Expression::Integer { span, .. } => span,
+            Expression::Boolean { span, .. } => span,
+            Expression::Reference { span, .. } => span,
+            Expression::Operation { span, .. } => span,
+            Expression::Assignment { span, .. } => span,
+            Expression::Call { span, .. } => span,
+            Expression::Error { span, .. } => span,
+        }
+    }
+
+    pub fn id(&self) -> NodeId {
+        *match self {
+            Expression::Integer { id, .. } => id,
+            Expression::Boolean { id, .. } => id,
+            Expression::Reference { id, .. } => id,
+            Expression::Operation { id, .. } => id,
+            Expression::Assignment { id, .. } => id,
+            Expression::Call { id, .. } => id,
+            Expression::Error { id, .. } => id,
+        }
+    }
 }
 
+#[derive(Debug)]
 pub enum Operation {
     Binary(BinaryOperation),
     Unary(UnaryOperation),
 }
 
+#[derive(Debug)]
 pub enum BinaryOperation {
     Add {
         lhs: Box<Expression>,
@@ -161,6 +252,177 @@ pub enum BinaryOperation {
     },
 }
 
+impl BinaryOperation {
+    pub fn new(operator: BinaryOperator, lhs: Box<Expression>, rhs: Box<Expression>) -> Self {
+        match operator {
+            BinaryOperator::Add => BinaryOperation::Add { lhs, rhs },
+            BinaryOperator::Sub => BinaryOperation::Sub { lhs, rhs },
+            BinaryOperator::Mul => BinaryOperation::Mul { lhs, rhs },
+            BinaryOperator::Div => BinaryOperation::Div { lhs, rhs },
+            BinaryOperator::Equal => BinaryOperation::Equal { lhs, rhs },
+            BinaryOperator::NotEqual => BinaryOperation::NotEqual { lhs, rhs },
+            BinaryOperator::Less => BinaryOperation::Less { lhs, rhs },
+            BinaryOperator::LessEqual => BinaryOperation::LessEqual { lhs, rhs },
+            BinaryOperator::Greater => BinaryOperation::Greater { lhs, rhs },
+            BinaryOperator::GreaterEqual => BinaryOperation::GreaterEqual { lhs, rhs },
+            BinaryOperator::And => BinaryOperation::And { lhs, rhs },
+            BinaryOperator::Or => BinaryOperation::Or { lhs, rhs },
+        }
+    }
+
+    pub fn operator(&self) -> BinaryOperator {
+        match self {
+            BinaryOperation::Add { .. } => BinaryOperator::Add,
+            BinaryOperation::Sub { .. } => BinaryOperator::Sub,
+            BinaryOperation::Mul { .. } => BinaryOperator::Mul,
+            BinaryOperation::Div { .. } => BinaryOperator::Div,
+            BinaryOperation::Equal { .. } => BinaryOperator::Equal,
+            BinaryOperation::NotEqual { .. } => BinaryOperator::NotEqual,
+            BinaryOperation::Less { .. } => BinaryOperator::Less,
+            BinaryOperation::LessEqual { .. } => BinaryOperator::LessEqual,
+            BinaryOperation::Greater { .. } => BinaryOperator::Greater,
+            BinaryOperation::GreaterEqual { .. } => BinaryOperator::GreaterEqual,
+            BinaryOperation::And { .. } => BinaryOperator::And,
+            BinaryOperation::Or { .. } => BinaryOperator::Or,
+        }
+    }
+
+    pub fn operands(&self) -> (&Expression, &Expression) {
+        match self {
+            BinaryOperation::Add { lhs, rhs }
+            | BinaryOperation::Sub { lhs, rhs }
+            | BinaryOperation::Mul { lhs, rhs }
+            | BinaryOperation::Div { lhs, rhs }
+            | BinaryOperation::Equal { lhs, rhs }
+            | BinaryOperation::NotEqual { lhs, rhs }
+            | BinaryOperation::Less { lhs, rhs }
+            | BinaryOperation::LessEqual { lhs, rhs }
+            | BinaryOperation::Greater { lhs, rhs }
+            | BinaryOperation::GreaterEqual { lhs, rhs }
+            | BinaryOperation::And { lhs, rhs }
+            | BinaryOperation::Or { lhs, rhs } => (lhs, rhs),
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> (&mut Expression, &mut Expression) {
+        match self {
+            BinaryOperation::Add { lhs, rhs }
+            | BinaryOperation::Sub { lhs, rhs }
+            | BinaryOperation::Mul { lhs, rhs }
+            | BinaryOperation::Div { lhs, rhs }
+            | BinaryOperation::Equal { lhs, rhs }
+            | BinaryOperation::NotEqual { lhs, rhs }
+            | BinaryOperation::Less { lhs, rhs }
+            | BinaryOperation::LessEqual { lhs, rhs }
+            | BinaryOperation::Greater { lhs, rhs }
+            | BinaryOperation::GreaterEqual { lhs, rhs }
+            | BinaryOperation::And { lhs, rhs }
+            | BinaryOperation::Or { lhs, rhs } => (lhs, rhs),
+        }
+    }
+}
+
+#[derive(Debug)]
 pub enum UnaryOperation {
     Not(Box<Expression>),
+    Negate(Box<Expression>),
+}
+
+impl UnaryOperation {
+    pub fn new(operator: UnaryOperator, operand: Box<Expression>) -> Self {
+        match operator {
+            UnaryOperator::Not => UnaryOperation::Not(operand),
+            UnaryOperator::Negate => UnaryOperation::Negate(operand),
+        }
+    }
+
+    pub fn operator(&self) -> UnaryOperator {
+        match self {
+            UnaryOperation::Not(_) => UnaryOperator::Not,
+            UnaryOperation::Negate(_) => UnaryOperator::Negate,
+        }
+    }
+
+    pub fn operand(&self) -> &Expression {
+        match self {
+            UnaryOperation::Not(operand) | UnaryOperation::Negate(operand) => operand,
+        }
+    }
+
+    pub fn operand_mut(&mut self) -> &mut Expression {
+        match self {
+            UnaryOperation::Not(operand) | UnaryOperation::Negate(operand) => operand,
+        }
+    }
+}
+
+/// The kind of a binary operation, without its operands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+}
+
+impl BinaryOperator {
+    pub const ALL: [BinaryOperator; 12] = [
+        BinaryOperator::Add,
+        BinaryOperator::Sub,
+        BinaryOperator::Mul,
+        BinaryOperator::Div,
+        BinaryOperator::Equal,
+        BinaryOperator::NotEqual,
+        BinaryOperator::Less,
+        BinaryOperator::LessEqual,
+        BinaryOperator::Greater,
+        BinaryOperator::GreaterEqual,
+        BinaryOperator::And,
+        BinaryOperator::Or,
+    ];
+
+    /// The operator as it is written in source.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Sub => "-",
+            BinaryOperator::Mul => "*",
+            BinaryOperator::Div => "/",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterEqual => ">=",
+            BinaryOperator::And => "&&",
+            BinaryOperator::Or => "||",
+        }
+    }
+}
+
+/// The kind of a unary operation, without its operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Not,
+    Negate,
+}
+
+impl UnaryOperator {
+    pub const ALL: [UnaryOperator; 2] = [UnaryOperator::Not, UnaryOperator::Negate];
+
+    /// The operator as it is written in source.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            UnaryOperator::Not => "!",
+            UnaryOperator::Negate => "-",
+        }
+    }
 }
@@ -0,0 +1,719 @@
+//! An alternative representation of the AST where nodes live in one vector
+//! per node type and refer to each other by index, and names are interned.
+//!
+//! The boxed tree in `ast` makes one allocation per node and one per name.
+//! Here a program is a handful of vectors, which is cheaper to build, to drop
+//! and to walk. Nodes are addressed by typed IDs, so an `ExpressionId` can
+//! never be used to look up a statement.
+
+use crate::ast::{BinaryOperator, UnaryOperator};
+use crate::parser::TreeBuilder;
+use crate::symbol::{Interner, Symbol};
+use std::marker::PhantomData;
+use std::ops::Index;
+use std::range::Range;
+
+macro_rules! arena_ids {
+    ($($(#[$attribute:meta])* $name:ident),* $(,)?) => {
+        $(
+            $(#[$attribute])*
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+            pub struct $name(u32);
+
+            impl $name {
+                fn new(index: usize) -> Self {
+                    Self(u32::try_from(index).expect("arena indices fit into `u32`"))
+                }
+
+                pub fn index(self) -> usize {
+                    self.0 as usize
+                }
+            }
+        )*
+    };
+}
+
+arena_ids! {
+    /// Indexes `Arena::items`.
+    ItemId,
+    /// Indexes `Arena::statements`.
+    StatementId,
+    /// Indexes `Arena::expressions`.
+    ExpressionId,
+}
+
+/// A run of consecutive entries in one of the arena's list pools, used for
+/// the children of blocks and calls and for function arguments.
+#[derive(Debug, PartialEq, Eq)]
+pub struct List<T> {
+    start: u32,
+    len: u32,
+    marker: PhantomData<T>,
+}
+
+impl<T> List<T> {
+    fn new(start: usize, len: usize) -> Self {
+        Self {
+            start: u32::try_from(start).expect("arena indices fit into `u32`"),
+            len: u32::try_from(len).expect("arena indices fit into `u32`"),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn range(&self) -> std::ops::Range<usize> {
+        self.start as usize..(self.start + self.len) as usize
+    }
+}
+
+impl<T> Clone for List<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for List<T> {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Function {
+        name: Symbol,
+        arguments: List<Symbol>,
+        body: StatementId,
+        span: Range<usize>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Block {
+        body: List<StatementId>,
+        span: Range<usize>,
+    },
+    Binding {
+        bind_to: Symbol,
+        value: ExpressionId,
+        span: Range<usize>,
+    },
+    Expression {
+        expression: ExpressionId,
+        span: Range<usize>,
+    },
+    Return {
+        value: ExpressionId,
+        span: Range<usize>,
+    },
+    Branch {
+        condition: ExpressionId,
+        then: StatementId,
+        otherwise: Option<StatementId>,
+        span: Range<usize>,
+    },
+    While {
+        condition: ExpressionId,
+        body: StatementId,
+        span: Range<usize>,
+    },
+    Error {
+        span: Range<usize>,
+    },
+}
+
+impl Statement {
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Statement::Block { span, .. }
+            | Statement::Binding { span, .. }
+            | Statement::Expression { span, .. }
+            | Statement::Return { span, .. }
+            | Statement::Branch { span, .. }
+            | Statement::While { span, .. }
+            | Statement::Error { span } => *span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Integer {
+        value: i64,
+        span: Range<usize>,
+    },
+    Boolean {
+        value: bool,
+        span: Range<usize>,
+    },
+    Reference {
+        name: Symbol,
+        span: Range<usize>,
+    },
+    Assignment {
+        assign_to: Symbol,
+        value: ExpressionId,
+        span: Range<usize>,
+    },
+    Unary {
+        operator: UnaryOperator,
+        operand: ExpressionId,
+        span: Range<usize>,
+    },
+    Binary {
+        operator: BinaryOperator,
+        lhs: ExpressionId,
+        rhs: ExpressionId,
+        span: Range<usize>,
+    },
+    Call {
+        name: Symbol,
+        arguments: List<ExpressionId>,
+        span: Range<usize>,
+    },
+    Error {
+        span: Range<usize>,
+    },
+}
+
+impl Expression {
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Expression::Integer { span, .. }
+            | Expression::Boolean { span, .. }
+            | Expression::Reference { span, .. }
+            | Expression::Assignment { span, .. }
+            | Expression::Unary { span, .. }
+            | Expression::Binary { span, .. }
+            | Expression::Call { span, .. }
+            | Expression::Error { span } => *span,
+        }
+    }
+}
+
+/// Owns every node of a program and the names they use.
+///
+/// Nodes of items that failed to parse stay in the arena but are not
+/// reachable from `items`.
+#[derive(Debug, Default)]
+pub struct Arena {
+    program: Vec<ItemId>,
+    items: Vec<Item>,
+    statements: Vec<Statement>,
+    expressions: Vec<Expression>,
+    statement_lists: Vec<StatementId>,
+    expression_lists: Vec<ExpressionId>,
+    symbol_lists: Vec<Symbol>,
+    interner: Interner,
+}
+
+impl Arena {
+    /// The items of the program in source order.
+    pub fn items(&self) -> &[ItemId] {
+        &self.program
+    }
+
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        self.interner.resolve(symbol)
+    }
+
+    fn push_item(&mut self, item: Item) -> ItemId {
+        self.items.push(item);
+        ItemId::new(self.items.len() - 1)
+    }
+
+    fn push_statement(&mut self, statement: Statement) -> StatementId {
+        self.statements.push(statement);
+        StatementId::new(self.statements.len() - 1)
+    }
+
+    fn push_expression(&mut self, expression: Expression) -> ExpressionId {
+        self.expressions.push(expression);
+        ExpressionId::new(self.expressions.len() - 1)
+    }
+}
+
+impl Index<ItemId> for Arena {
+    type Output = Item;
+
+    fn index(&self, id: ItemId) -> &Item {
+        &self.items[id.index()]
+    }
+}
+
+impl Index<StatementId> for Arena {
+    type Output = Statement;
+
+    fn index(&self, id: StatementId) -> &Statement {
+        &self.statements[id.index()]
+    }
+}
+
+impl Index<ExpressionId> for Arena {
+    type Output = Expression;
+
+    fn index(&self, id: ExpressionId) -> &Expression {
+        &self.expressions[id.index()]
+    }
+}
+
+impl Index<List<StatementId>> for Arena {
+    type Output = [StatementId];
+
+    fn index(&self, list: List<StatementId>) -> &[StatementId] {
+        &self.statement_lists[list.range()]
+    }
+}
+
+impl Index<List<ExpressionId>> for Arena {
+    type Output = [ExpressionId];
+
+    fn index(&self, list: List<ExpressionId>) -> &[ExpressionId] {
+        &self.expression_lists[list.range()]
+    }
+}
+
+impl Index<List<Symbol>> for Arena {
+    type Output = [Symbol];
+
+    fn index(&self, list: List<Symbol>) -> &[Symbol] {
+        &self.symbol_lists[list.range()]
+    }
+}
+
+/// Builds an `Arena` while parsing.
+#[derive(Debug, Default)]
+pub struct ArenaBuilder {
+    arena: Arena,
+}
+
+impl ArenaBuilder {
+    fn list<T: Copy>(pool: &mut Vec<T>, entries: impl IntoIterator<Item = T>) -> List<T> {
+        let start = pool.len();
+        pool.extend(entries);
+
+        List::new(start, pool.len() - start)
+    }
+}
+
+impl TreeBuilder for ArenaBuilder {
+    type Program = Arena;
+    type Item = ItemId;
+    type Statement = StatementId;
+    type Expression = ExpressionId;
+
+    fn statement_span(&self, statement: &StatementId) -> Range<usize> {
+        self.arena[*statement].span()
+    }
+
+    fn expression_span(&self, expression: &ExpressionId) -> Range<usize> {
+        self.arena[*expression].span()
+    }
+
+    fn program(&mut self, items: Vec<ItemId>) -> Arena {
+        let mut arena = std::mem::take(&mut self.arena);
+        arena.program = items;
+
+        arena
+    }
+
+    fn function(
+        &mut self,
+        name: String,
+        arguments: Vec<String>,
+        body: StatementId,
+        span: Range<usize>,
+    ) -> ItemId {
+        let name = self.arena.interner.intern(&name);
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.arena.interner.intern(argument))
+            .collect::<Vec<_>>();
+        let arguments = Self::list(&mut self.arena.symbol_lists, arguments);
+
+        self.arena.push_item(Item::Function {
+            name,
+            arguments,
+            body,
+            span,
+        })
+    }
+
+    fn block(&mut self, body: Vec<StatementId>, span: Range<usize>) -> StatementId {
+        let body = Self::list(&mut self.arena.statement_lists, body);
+
+        self.arena.push_statement(Statement::Block { body, span })
+    }
+
+    fn binding(&mut self, bind_to: String, value: ExpressionId, span: Range<usize>) -> StatementId {
+        let bind_to = self.arena.interner.intern(&bind_to);
+
+        self.arena.push_statement(Statement::Binding {
+            bind_to,
+            value,
+            span,
+        })
+    }
+
+    fn expression_statement(
+        &mut self,
+        expression: ExpressionId,
+        span: Range<usize>,
+    ) -> StatementId {
+        self.arena
+            .push_statement(Statement::Expression { expression, span })
+    }
+
+    fn return_statement(&mut self, value: ExpressionId, span: Range<usize>) -> StatementId {
+        self.arena.push_statement(Statement::Return { value, span })
+    }
+
+    fn branch(
+        &mut self,
+        condition: ExpressionId,
+        then: StatementId,
+        otherwise: Option<StatementId>,
+        span: Range<usize>,
+    ) -> StatementId {
+        self.arena.push_statement(Statement::Branch {
+            condition,
+            then,
+            otherwise,
+            span,
+        })
+    }
+
+    fn while_loop(
+        &mut self,
+        condition: ExpressionId,
+        body: StatementId,
+        span: Range<usize>,
+    ) -> StatementId {
+        self.arena.push_statement(Statement::While {
+            condition,
+            body,
+            span,
+        })
+    }
+
+    fn error_statement(&mut self, span: Range<usize>) -> StatementId {
+        self.arena.push_statement(Statement::Error { span })
+    }
+
+    fn integer(&mut self, value: i64, span: Range<usize>) -> ExpressionId {
+        self.arena
+            .push_expression(Expression::Integer { value, span })
+    }
+
+    fn boolean(&mut self, value: bool, span: Range<usize>) -> ExpressionId {
+        self.arena
+            .push_expression(Expression::Boolean { value, span })
+    }
+
+    fn reference(&mut self, name: String, span: Range<usize>) -> ExpressionId {
+        let name = self.arena.interner.intern(&name);
+
+        self.arena
+            .push_expression(Expression::Reference { name, span })
+    }
+
+    fn unary(
+        &mut self,
+        operator: UnaryOperator,
+        operand: ExpressionId,
+        span: Range<usize>,
+    ) -> ExpressionId {
+        self.arena.push_expression(Expression::Unary {
+            operator,
+            operand,
+            span,
+        })
+    }
+
+    fn binary(
+        &mut self,
+        operator: BinaryOperator,
+        lhs: ExpressionId,
+        rhs: ExpressionId,
+        span: Range<usize>,
+    ) -> ExpressionId {
+        self.arena.push_expression(Expression::Binary {
+            operator,
+            lhs,
+            rhs,
+            span,
+        })
+    }
+
+    fn assignment(
+        &mut self,
+        target: ExpressionId,
+        value: ExpressionId,
+        span: Range<usize>,
+    ) -> Option<ExpressionId> {
+        let Expression::Reference { name, .. } = self.arena[target] else {
+            return None;
+        };
+
+        Some(self.arena.push_expression(Expression::Assignment {
+            assign_to: name,
+            value,
+            span,
+        }))
+    }
+
+    fn call(
+        &mut self,
+        name: String,
+        arguments: Vec<ExpressionId>,
+        span: Range<usize>,
+    ) -> ExpressionId {
+        let name = self.arena.interner.intern(&name);
+        let arguments = Self::list(&mut self.arena.expression_lists, arguments);
+
+        self.arena.push_expression(Expression::Call {
+            name,
+            arguments,
+            span,
+        })
+    }
+
+    fn error_expression(&mut self, span: Range<usize>) -> ExpressionId {
+        self.arena.push_expression(Expression::Error { span })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn parse(source: &str) -> (Arena, ast::Program) {
+        let (arena, errors) =
+            Parser::with_builder(Lexer::new(source), ArenaBuilder::default()).parse();
+        let (program, boxed_errors) = Parser::new(Lexer::new(source)).parse();
+        assert_eq!(format!("{errors:?}"), format!("{boxed_errors:?}"));
+
+        (arena, program)
+    }
+
+    fn assert_same_statement(arena: &Arena, id: StatementId, boxed: &ast::Statement) {
+        assert_eq!(arena[id].span(), boxed.span());
+
+        match (&arena[id], boxed) {
+            (Statement::Block { body, .. }, ast::Statement::Block { body: boxed, .. }) => {
+                assert_eq!(body.len(), boxed.len());
+                for (&id, boxed) in arena[*body].iter().zip(boxed) {
+                    assert_same_statement(arena, id, boxed);
+                }
+            }
+            (
+                Statement::Binding { bind_to, value, .. },
+                ast::Statement::Binding {
+                    bind_to: name,
+                    value: boxed,
+                    ..
+                },
+            ) => {
+                assert_eq!(arena.resolve(*bind_to), name);
+                assert_same_expression(arena, *value, boxed);
+            }
+            (
+                Statement::Expression { expression, .. },
+                ast::Statement::Expression {
+                    expression: boxed, ..
+                },
+            )
+            | (
+                Statement::Return {
+                    value: expression, ..
+                },
+                ast::Statement::Return { value: boxed, .. },
+            ) => {
+                assert_same_expression(arena, *expression, boxed);
+            }
+            (
+                Statement::Branch {
+                    condition,
+                    then,
+                    otherwise,
+                    ..
+                },
+                ast::Statement::Branch {
+                    condition: boxed_condition,
+                    then: boxed_then,
+                    otherwise: boxed_otherwise,
+                    ..
+                },
+            ) => {
+                assert_same_expression(arena, *condition, boxed_condition);
+                assert_same_statement(arena, *then, boxed_then);
+                match (otherwise, boxed_otherwise) {
+                    (Some(otherwise), Some(boxed)) => {
+                        assert_same_statement(arena, *otherwise, boxed)
+                    }
+                    (None, None) => {}
+                    _ => panic!("only one of the branches has an `else`"),
+                }
+            }
+            (
+                Statement::While {
+                    condition, body, ..
+                },
+                ast::Statement::While {
+                    condition: boxed_condition,
+                    body: boxed_body,
+                    ..
+                },
+            ) => {
+                assert_same_expression(arena, *condition, boxed_condition);
+                assert_same_statement(arena, *body, boxed_body);
+            }
+            (Statement::Error { .. }, ast::Statement::Error { .. }) => {}
+            (statement, boxed) => panic!("{statement:?} differs from {boxed:?}"),
+        }
+    }
+
+    fn assert_same_expression(arena: &Arena, id: ExpressionId, boxed: &ast::Expression) {
+        assert_eq!(arena[id].span(), boxed.span());
+
+        match (&arena[id], boxed) {
+            (Expression::Integer { value, .. }, ast::Expression::Integer { value: boxed, .. }) => {
+                assert_eq!(value, boxed);
+            }
+            (Expression::Boolean { value, .. }, ast::Expression::Boolean { value: boxed, .. }) => {
+                assert_eq!(value, boxed);
+            }
+            (
+                Expression::Reference { name, .. },
+                ast::Expression::Reference { name: boxed, .. },
+            ) => {
+                assert_eq!(arena.resolve(*name), boxed);
+            }
+            (
+                Expression::Assignment {
+                    assign_to, value, ..
+                },
+                ast::Expression::Assignment {
+                    assign_to: name,
+                    value: boxed,
+                    ..
+                },
+            ) => {
+                assert_eq!(arena.resolve(*assign_to), name);
+                assert_same_expression(arena, *value, boxed);
+            }
+            (
+                Expression::Unary {
+                    operator, operand, ..
+                },
+                ast::Expression::Operation {
+                    operation: ast::Operation::Unary(operation),
+                    ..
+                },
+            ) => {
+                assert_eq!(*operator, operation.operator());
+                assert_same_expression(arena, *operand, operation.operand());
+            }
+            (
+                Expression::Binary {
+                    operator, lhs, rhs, ..
+                },
+                ast::Expression::Operation {
+                    operation: ast::Operation::Binary(operation),
+                    ..
+                },
+            ) => {
+                let (boxed_lhs, boxed_rhs) = operation.operands();
+                assert_eq!(*operator, operation.operator());
+                assert_same_expression(arena, *lhs, boxed_lhs);
+                assert_same_expression(arena, *rhs, boxed_rhs);
+            }
+            (
+                Expression::Call {
+                    name, arguments, ..
+                },
+                ast::Expression::Call {
+                    name: boxed_name,
+                    arguments: boxed,
+                    ..
+                },
+            ) => {
+                assert_eq!(arena.resolve(*name), boxed_name);
+                assert_eq!(arguments.len(), boxed.len());
+                for (&id, boxed) in arena[*arguments].iter().zip(boxed) {
+                    assert_same_expression(arena, id, boxed);
+                }
+            }
+            (Expression::Error { .. }, ast::Expression::Error { .. }) => {}
+            (expression, boxed) => panic!("{expression:?} differs from {boxed:?}"),
+        }
+    }
+
+    fn assert_same_program(source: &str) {
+        let (arena, program) = parse(source);
+        assert_eq!(arena.items().len(), program.items().len());
+
+        for (&id, boxed) in arena.items().iter().zip(program.items()) {
+            let Item::Function {
+                name,
+                arguments,
+                body,
+                span,
+            } = &arena[id];
+            let ast::Item::Function {
+                name: boxed_name,
+                arguments: boxed_arguments,
+                body: boxed_body,
+                span: boxed_span,
+                ..
+            } = boxed;
+
+            assert_eq!(arena.resolve(*name), boxed_name);
+            assert_eq!(
+                arena[*arguments]
+                    .iter()
+                    .map(|&argument| arena.resolve(argument))
+                    .collect::<Vec<_>>(),
+                *boxed_arguments
+            );
+            assert_eq!(span, boxed_span);
+            assert_same_statement(&arena, *body, boxed_body);
+        }
+    }
+
+    #[test]
+    fn arena_matches_the_boxed_tree() {
+        assert_same_program(
+            "fn f(a, b) { let x = -a + 1 * -2; while x < b && !false { x = g(x, b); } \
+             if x == 3 { return x; } else { f(); } return (x); } fn g(y) { if !y {} }",
+        );
+    }
+
+    #[test]
+    fn arena_matches_the_boxed_tree_after_errors() {
+        assert_same_program(
+            "fn f() { let = 1; return 2 +; 1 = 2; } fn (x) {} fn g() { return 3; }",
+        );
+    }
+
+    #[test]
+    fn names_are_interned_once() {
+        let (arena, _) = parse("fn f(x) { let y = x + x; return f(y); }");
+
+        assert_eq!(arena.interner().len(), 3);
+        let x = arena.interner().get("x").unwrap();
+        let Item::Function { arguments, .. } = &arena[arena.items()[0]];
+        assert_eq!(arena[*arguments], [x]);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::ast::id::NodeId;
+use crate::ast::{
+    BinaryOperation, BinaryOperator, Expression, Item, Operation, Program, Statement,
+    UnaryOperation, UnaryOperator,
+};
+use crate::parser::TreeBuilder;
+use std::range::Range;
+
+/// Builds the boxed `Program` tree and numbers its nodes.
+#[derive(Debug, Default)]
+pub struct BoxBuilder {
+    next_id: u32,
+}
+
+impl BoxBuilder {
+    /// Hands out the ID for the node about to be created. Nodes are created
+    /// after their children, so children have the lower IDs.
+    fn node_id(&mut self) -> NodeId {
+        let id = NodeId::new(self.next_id);
+        self.next_id += 1;
+
+        id
+    }
+}
+
+impl TreeBuilder for BoxBuilder {
+    type Program = Program;
+    type Item = Item;
+    type Statement = Statement;
+    type Expression = Expression;
+
+    fn statement_span(&self, statement: &Statement) -> Range<usize> {
+        statement.span()
+    }
+
+    fn expression_span(&self, expression: &Expression) -> Range<usize> {
+        expression.span()
+    }
+
+    fn program(&mut self, items: Vec<Item>) -> Program {
+        Program::new(items)
+    }
+
+    fn function(
+        &mut self,
+        name: String,
+        arguments: Vec<String>,
+        body: Statement,
+        span: Range<usize>,
+    ) -> Item {
+        Item::Function {
+            name,
+            arguments,
+            body,
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn block(&mut self, body: Vec<Statement>, span: Range<usize>) -> Statement {
+        Statement::Block {
+            body,
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn binding(&mut self, bind_to: String, value: Expression, span: Range<usize>) -> Statement {
+        Statement::Binding {
+            bind_to,
+            value,
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn expression_statement(&mut self, expression: Expression, span: Range<usize>) -> Statement {
+        Statement::Expression {
+            expression,
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn return_statement(&mut self, value: Expression, span: Range<usize>) -> Statement {
+        Statement::Return {
+            value,
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn branch(
+        &mut self,
+        condition: Expression,
+        then: Statement,
+        otherwise: Option<Statement>,
+        span: Range<usize>,
+    ) -> Statement {
+        Statement::Branch {
+            condition,
+            then: Box::new(then),
+            otherwise: otherwise.map(Box::new),
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn while_loop(
+        &mut self,
+        condition: Expression,
+        body: Statement,
+        span: Range<usize>,
+    ) -> Statement {
+        Statement::While {
+            condition,
+            body: Box::new(body),
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn error_statement(&mut self, span: Range<usize>) -> Statement {
+        Statement::Error {
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn integer(&mut self, value: i64, span: Range<usize>) -> Expression {
+        Expression::Integer {
+            value,
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn boolean(&mut self, value: bool, span: Range<usize>) -> Expression {
+        Expression::Boolean {
+            value,
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn reference(&mut self, name: String, span: Range<usize>) -> Expression {
+        Expression::Reference {
+            name,
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn unary(
+        &mut self,
+        operator: UnaryOperator,
+        operand: Expression,
+        span: Range<usize>,
+    ) -> Expression {
+        Expression::Operation {
+            operation: Operation::Unary(UnaryOperation::new(operator, Box::new(operand))),
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn binary(
+        &mut self,
+        operator: BinaryOperator,
+        lhs: Expression,
+        rhs: Expression,
+        span: Range<usize>,
+    ) -> Expression {
+        Expression::Operation {
+            operation: Operation::Binary(BinaryOperation::new(
+                operator,
+                Box::new(lhs),
+                Box::new(rhs),
+            )),
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn assignment(
+        &mut self,
+        target: Expression,
+        value: Expression,
+        span: Range<usize>,
+    ) -> Option<Expression> {
+        let Expression::Reference { name, .. } = target else {
+            return None;
+        };
+
+        Some(Expression::Assignment {
+            assign_to: name,
+            value: Box::new(value),
+            span,
+            id: self.node_id(),
+        })
+    }
+
+    fn call(&mut self, name: String, arguments: Vec<Expression>, span: Range<usize>) -> Expression {
+        Expression::Call {
+            name,
+            arguments,
+            span,
+            id: self.node_id(),
+        }
+    }
+
+    fn error_expression(&mut self, span: Range<usize>) -> Expression {
+        Expression::Error {
+            span,
+            id: self.node_id(),
+        }
+    }
+}
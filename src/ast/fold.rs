@@ -0,0 +1,414 @@
+//! Tree-to-tree rewrites of the AST.
+//!
+//! A `Folder` takes every node by value and returns its replacement. The
+//! default methods call the matching `walk_*` function, which folds the
+//! children and rebuilds the node with its original span.
+
+use crate::ast::{
+    BinaryOperation, Expression, Item, Operation, Program, Statement, UnaryOperation,
+};
+
+pub trait Folder {
+    fn fold_program(&mut self, program: Program) -> Program {
+        walk_program(self, program)
+    }
+
+    fn fold_item(&mut self, item: Item) -> Item {
+        walk_item(self, item)
+    }
+
+    fn fold_statement(&mut self, statement: Statement) -> Statement {
+        walk_statement(self, statement)
+    }
+
+    fn fold_expression(&mut self, expression: Expression) -> Expression {
+        walk_expression(self, expression)
+    }
+
+    fn fold_operation(&mut self, operation: Operation) -> Operation {
+        walk_operation(self, operation)
+    }
+
+    fn fold_binary_operation(&mut self, operation: BinaryOperation) -> BinaryOperation {
+        walk_binary_operation(self, operation)
+    }
+
+    fn fold_unary_operation(&mut self, operation: UnaryOperation) -> UnaryOperation {
+        walk_unary_operation(self, operation)
+    }
+}
+
+pub fn walk_program<F: Folder + ?Sized>(folder: &mut F, program: Program) -> Program {
+    let items = program
+        .into_items()
+        .into_iter()
+        .map(|item| folder.fold_item(item))
+        .collect();
+
+    Program::new(items)
+}
+
+pub fn walk_item<F: Folder + ?Sized>(folder: &mut F, item: Item) -> Item {
+    match item {
+        Item::Function {
+            name,
+            arguments,
+            body,
+            span,
+            id,
+        } => Item::Function {
+            name,
+            arguments,
+            body: folder.fold_statement(body),
+            span,
+            id,
+        },
+    }
+}
+
+pub fn walk_statement<F: Folder + ?Sized>(folder: &mut F, statement: Statement) -> Statement {
+    match statement {
+        Statement::Block { body, span, id } => Statement::Block {
+            body: body
+                .into_iter()
+                .map(|statement| folder.fold_statement(statement))
+                .collect(),
+            span,
+            id,
+        },
+        Statement::Binding {
+            bind_to,
+            value,
+            span,
+            id,
+        } => Statement::Binding {
+            bind_to,
+            value: folder.fold_expression(value),
+            span,
+            id,
+        },
+        Statement::Expression {
+            expression,
+            span,
+            id,
+        } => Statement::Expression {
+            expression: folder.fold_expression(expression),
+            span,
+            id,
+        },
+        Statement::Return { value, span, id } => Statement::Return {
+            value: folder.fold_expression(value),
+            span,
+            id,
+        },
+        Statement::Branch {
+            condition,
+            then,
+            otherwise,
+            span,
+            id,
+        } => Statement::Branch {
+            condition: folder.fold_expression(condition),
+            then: Box::new(folder.fold_statement(*then)),
+            otherwise: otherwise.map(|otherwise| Box::new(folder.fold_statement(*otherwise))),
+            span,
+            id,
+        },
+        Statement::While {
+            condition,
+            body,
+            span,
+            id,
+        } => Statement::While {
+            condition: folder.fold_expression(condition),
+            body: Box::new(folder.fold_statement(*body)),
+            span,
+            id,
+        },
+        Statement::Error { span, id } => Statement::Error { span, id },
+    }
+}
+
+pub fn walk_expression<F: Folder + ?Sized>(folder: &mut F, expression: Expression) -> Expression {
+    match expression {
+        Expression::Integer { .. }
+        | Expression::Boolean { .. }
+        | Expression::Reference { .. }
+        | Expression::Error { .. } => expression,
+        Expression::Operation {
+            operation,
+            span,
+            id,
+        } => Expression::Operation {
+            operation: folder.fold_operation(operation),
+            span,
+            id,
+        },
+        Expression::Assignment {
+            assign_to,
+            value,
+            span,
+            id,
+        } => Expression::Assignment {
+            assign_to,
+            value: Box::new(folder.fold_expression(*value)),
+            span,
+            id,
+        },
+        Expression::Call {
+            name,
+            arguments,
+            span,
+            id,
+        } => Expression::Call {
+            name,
+            arguments: arguments
+                .into_iter()
+                .map(|argument| folder.fold_expression(argument))
+                .collect(),
+            span,
+            id,
+        },
+    }
+}
+
+pub fn walk_operation<F: Folder + ?Sized>(folder: &mut F, operation: Operation) -> Operation {
+    match operation {
+        Operation::Binary(operation) => Operation::Binary(folder.fold_binary_operation(operation)),
+        Operation::Unary(operation) => Operation::Unary(folder.fold_unary_operation(operation)),
+    }
+}
+
+pub fn walk_binary_operation<F: Folder + ?Sized>(
+    folder: &mut F,
+    operation: BinaryOperation,
+) -> BinaryOperation {
+    let mut fold = |operand: Box<Expression>| Box::new(folder.fold_expression(*operand));
+
+    match operation {
+        BinaryOperation::Add { lhs, rhs } => BinaryOperation::Add {
+            lhs: fold(lhs),
+            rhs: fold(rhs),
+        },
+        BinaryOperation::Sub { lhs, rhs } => BinaryOperation::Sub {
+            lhs: fold(lhs),
+            rhs: fold(rhs),
+        },
+        BinaryOperation::Mul { lhs, rhs } => BinaryOperation::Mul {
+            lhs: fold(lhs),
+            rhs: fold(rhs),
+        },
+        BinaryOperation::Div { lhs, rhs } => BinaryOperation::Div {
+            lhs: fold(lhs),
+            rhs: fold(rhs),
+        },
+        BinaryOperation::Equal { lhs, rhs } => BinaryOperation::Equal {
+            lhs: fold(lhs),
+            rhs: fold(rhs),
+        },
+        BinaryOperation::NotEqual { lhs, rhs } => BinaryOperation::NotEqual {
+            lhs: fold(lhs),
+            rhs: fold(rhs),
+        },
+        BinaryOperation::Less { lhs, rhs } => BinaryOperation::Less {
+            lhs: fold(lhs),
+            rhs: fold(rhs),
+        },
+        BinaryOperation::LessEqual { lhs, rhs } => BinaryOperation::LessEqual {
+            lhs: fold(lhs),
+            rhs: fold(rhs),
+        },
+        BinaryOperation::Greater { lhs, rhs } => BinaryOperation::Greater {
+            lhs: fold(lhs),
+            rhs: fold(rhs),
+        },
+        BinaryOperation::GreaterEqual { lhs, rhs } => BinaryOperation::GreaterEqual {
+            lhs: fold(lhs),
+            rhs: fold(rhs),
+        },
+        BinaryOperation::And { lhs, rhs } => BinaryOperation::And {
+            lhs: fold(lhs),
+            rhs: fold(rhs),
+        },
+        BinaryOperation::Or { lhs, rhs } => BinaryOperation::Or {
+            lhs: fold(lhs),
+            rhs: fold(rhs),
+        },
+    }
+}
+
+pub fn walk_unary_operation<F: Folder + ?Sized>(
+    folder: &mut F,
+    operation: UnaryOperation,
+) -> UnaryOperation {
+    match operation {
+        UnaryOperation::Not(operand) => {
+            UnaryOperation::Not(Box::new(folder.fold_expression(*operand)))
+        }
+        UnaryOperation::Negate(operand) => {
+            UnaryOperation::Negate(Box::new(folder.fold_expression(*operand)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::TreePrinter;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn parse(source: &str) -> Program {
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+        assert!(errors.is_empty(), "`{source}` failed to parse: {errors:?}");
+
+        program
+    }
+
+    fn print(program: &Program) -> String {
+        TreePrinter::new(true).print_program(program)
+    }
+
+    /// Replaces branches on a literal condition by the branch that is taken.
+    struct ConstantBranches;
+
+    impl Folder for ConstantBranches {
+        fn fold_statement(&mut self, statement: Statement) -> Statement {
+            // fold the children first, so nested branches are resolved too
+            match walk_statement(self, statement) {
+                Statement::Branch {
+                    condition: Expression::Boolean { value, .. },
+                    then,
+                    otherwise,
+                    span,
+                    id,
+                } => match (value, otherwise) {
+                    (true, _) => *then,
+                    (false, Some(otherwise)) => *otherwise,
+                    // the empty block takes the place of the branch
+                    (false, None) => Statement::Block {
+                        body: Vec::new(),
+                        span,
+                        id,
+                    },
+                },
+                statement => statement,
+            }
+        }
+    }
+
+    #[test]
+    fn removes_constant_branches() {
+        let folded = ConstantBranches.fold_program(parse(
+            "fn f() { if true { if false { return 1; } else { return 2; } } if false { return 3; } }",
+        ));
+        let expected = parse("fn f() { { { return 2; } } {} }");
+
+        assert_eq!(
+            TreePrinter::new(false).print_program(&folded),
+            TreePrinter::new(false).print_program(&expected)
+        );
+        assert_eq!(
+            print(&folded),
+            "\
+Program
+|- Function-Item @ 0..87
+   |- name: f
+   |- parameters: [],
+   |- body: Block @ 7..87
+            |- Block @ 17..62
+               |- Block @ 47..60
+                  |- Return-Stmt @ 49..58
+                     |- value: Int-Expression @ 56..57
+                               |- value: 2
+            |- Block @ 63..85
+"
+        );
+    }
+
+    /// Rewrites `!(a == b)` to `a != b` and `!(a != b)` to `a == b`.
+    struct NegatedComparisons;
+
+    impl Folder for NegatedComparisons {
+        fn fold_expression(&mut self, expression: Expression) -> Expression {
+            let Expression::Operation {
+                operation: Operation::Unary(UnaryOperation::Not(operand)),
+                span,
+                id,
+            } = expression
+            else {
+                return walk_expression(self, expression);
+            };
+
+            let operation = match *operand {
+                Expression::Operation {
+                    operation: Operation::Binary(BinaryOperation::Equal { lhs, rhs }),
+                    ..
+                } => BinaryOperation::NotEqual { lhs, rhs },
+                Expression::Operation {
+                    operation: Operation::Binary(BinaryOperation::NotEqual { lhs, rhs }),
+                    ..
+                } => BinaryOperation::Equal { lhs, rhs },
+                operand => {
+                    let operand = self.fold_expression(operand);
+                    return Expression::Operation {
+                        operation: Operation::Unary(UnaryOperation::Not(Box::new(operand))),
+                        span,
+                        id,
+                    };
+                }
+            };
+
+            let operation = self.fold_binary_operation(operation);
+            Expression::Operation {
+                operation: Operation::Binary(operation),
+                span,
+                id,
+            }
+        }
+    }
+
+    #[test]
+    fn rewrites_negated_comparisons() {
+        let folded =
+            NegatedComparisons.fold_program(parse("fn f() { return !(a == !(b != c)) && !x; }"));
+
+        assert_eq!(
+            print(&folded),
+            "\
+Program
+|- Function-Item @ 0..42
+   |- name: f
+   |- parameters: [],
+   |- body: Block @ 7..42
+            |- Return-Stmt @ 9..40
+               |- value: And-Operation @ 16..39
+                         |- lhs: NotEqual-Operation @ 16..31
+                                 |- lhs: Reference-Expression @ 18..19
+                                         |- name: a
+                                 |- rhs: Equal-Operation @ 23..31
+                                         |- lhs: Reference-Expression @ 25..26
+                                                 |- name: b
+                                         |- rhs: Reference-Expression @ 30..31
+                                                 |- name: c
+                         |- rhs: Not-Operation @ 37..39
+                                 |- operand: Reference-Expression @ 38..39
+                                             |- name: x
+"
+        );
+    }
+
+    struct Identity;
+
+    impl Folder for Identity {}
+
+    #[test]
+    fn identity_keeps_the_tree() {
+        let source = include_str!("../../examples/example2.inv");
+
+        assert_eq!(
+            print(&Identity.fold_program(parse(source))),
+            print(&parse(source))
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::fmt;
+use std::ops::Index;
+
+/// Identifies an item, statement or expression within one parsed program.
+///
+/// The parser numbers nodes in the order it finishes them, so parsing the
+/// same source twice gives the same IDs. Analysis passes record their results
+/// in a `NodeMap` under these IDs instead of changing the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    pub fn new(index: u32) -> Self {
+        Self(index)
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// A side table that associates nodes with values of type `T`.
+///
+/// Node IDs are dense, so the table is a vector indexed by them.
+#[derive(Debug, Clone)]
+pub struct NodeMap<T> {
+    entries: Vec<Option<T>>,
+    len: usize,
+}
+
+impl<T> NodeMap<T> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Records `value` for `id`, returning the value recorded before.
+    pub fn insert(&mut self, id: NodeId, value: T) -> Option<T> {
+        if id.index() >= self.entries.len() {
+            self.entries.resize_with(id.index() + 1, || None);
+        }
+
+        let previous = self.entries[id.index()].replace(value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+
+        previous
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&T> {
+        self.entries.get(id.index())?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut T> {
+        self.entries.get_mut(id.index())?.as_mut()
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn remove(&mut self, id: NodeId) -> Option<T> {
+        let removed = self.entries.get_mut(id.index())?.take();
+        if removed.is_some() {
+            self.len -= 1;
+        }
+
+        removed
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The recorded entries in the order of their IDs.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &T)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, value)| {
+                let id = NodeId::new(u32::try_from(index).expect("node IDs fit into `u32`"));
+                value.as_ref().map(|value| (id, value))
+            })
+    }
+}
+
+impl<T> Default for NodeMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<NodeId> for NodeMap<T> {
+    type Output = T;
+
+    fn index(&self, id: NodeId) -> &T {
+        self.get(id)
+            .unwrap_or_else(|| panic!("no entry for node {id}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::visit::{Visitor, walk_expression, walk_item, walk_statement};
+    use crate::ast::{BinaryOperation, Expression, Item, Operation, Program, Statement};
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use std::collections::HashSet;
+
+    fn parse(source: &str) -> Program {
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+        assert!(errors.is_empty(), "`{source}` failed to parse: {errors:?}");
+
+        program
+    }
+
+    /// Collects the ID of every item, statement and expression.
+    #[derive(Default)]
+    struct Ids(Vec<NodeId>);
+
+    impl Visitor for Ids {
+        fn visit_item(&mut self, item: &Item) {
+            self.0.push(item.id());
+            walk_item(self, item);
+        }
+
+        fn visit_statement(&mut self, statement: &Statement) {
+            self.0.push(statement.id());
+            walk_statement(self, statement);
+        }
+
+        fn visit_expression(&mut self, expression: &Expression) {
+            self.0.push(expression.id());
+            walk_expression(self, expression);
+        }
+    }
+
+    fn ids(program: &Program) -> Vec<NodeId> {
+        let mut ids = Ids::default();
+        ids.visit_program(program);
+
+        ids.0
+    }
+
+    #[test]
+    fn parser_assigns_unique_and_stable_ids() {
+        let source =
+            "fn f(a) { let x = -a + 1; while x < 3 { x = g(x); } return x; } fn g(y) { if !y {} }";
+        let first = ids(&parse(source));
+
+        let unique = first.iter().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), first.len());
+        assert_eq!(first, ids(&parse(source)));
+    }
+
+    /// Records the value of every expression that is a constant.
+    #[derive(Default)]
+    struct Constants(NodeMap<i64>);
+
+    impl Visitor for Constants {
+        fn visit_expression(&mut self, expression: &Expression) {
+            walk_expression(self, expression);
+
+            let value = match expression {
+                Expression::Integer { value, .. } => Some(*value),
+                Expression::Operation {
+                    operation: Operation::Binary(operation),
+                    ..
+                } => {
+                    let (lhs, rhs) = operation.operands();
+                    let lhs = self.0.get(lhs.id()).copied();
+                    let rhs = self.0.get(rhs.id()).copied();
+                    match (operation, lhs, rhs) {
+                        (BinaryOperation::Add { .. }, Some(lhs), Some(rhs)) => lhs.checked_add(rhs),
+                        (BinaryOperation::Mul { .. }, Some(lhs), Some(rhs)) => lhs.checked_mul(rhs),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+
+            if let Some(value) = value {
+                self.0.insert(expression.id(), value);
+            }
+        }
+    }
+
+    #[test]
+    fn side_tables_annotate_without_changing_the_tree() {
+        let program = parse("fn f(x) { return 2 * 3 + x; }");
+        let mut constants = Constants::default();
+        constants.visit_program(&program);
+
+        let Item::Function { body, .. } = &program.items()[0];
+        let Statement::Block { body, .. } = body else {
+            panic!("function bodies are blocks");
+        };
+        let Statement::Return { value, .. } = &body[0] else {
+            panic!("expected a return");
+        };
+        let Expression::Operation {
+            operation: Operation::Binary(sum),
+            ..
+        } = value
+        else {
+            panic!("expected a sum");
+        };
+        let (product, x) = sum.operands();
+
+        assert_eq!(constants.0[product.id()], 6);
+        assert!(!constants.0.contains(x.id()));
+        assert!(!constants.0.contains(value.id()));
+        assert_eq!(constants.0.len(), 3);
+    }
+
+    #[test]
+    fn node_map_replaces_and_removes_entries() {
+        let mut map = NodeMap::new();
+        assert_eq!(map.insert(NodeId::new(4), "a"), None);
+        assert_eq!(map.insert(NodeId::new(1), "b"), None);
+        assert_eq!(map.insert(NodeId::new(4), "c"), Some("a"));
+
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [(NodeId::new(1), &"b"), (NodeId::new(4), &"c")]
+        );
+        assert_eq!(map.remove(NodeId::new(1)), Some("b"));
+        assert_eq!(map.remove(NodeId::new(1)), None);
+        assert_eq!(map.get(NodeId::new(9)), None);
+        assert_eq!(map.len(), 1);
+    }
+}
@@ -0,0 +1,325 @@
+use crate::ast::{
+    BinaryOperation, Expression, Item, Operation, Program, Statement, UnaryOperation,
+};
+use std::fmt::{Display, Write};
+use std::range::Range;
+
+/// Renders the AST as an indented tree, the format used by `examples/*.ast`.
+///
+/// Every node is printed as its label, followed by one `|- ` line per child.
+/// Children line up with the first character of their parent's label.
+pub struct TreePrinter {
+    spans: bool,
+    out: String,
+}
+
+impl TreePrinter {
+    /// Creates a printer that appends ` @ start..end` to every node label if
+    /// `spans` is set.
+    pub fn new(spans: bool) -> Self {
+        Self {
+            spans,
+            out: String::new(),
+        }
+    }
+
+    pub fn print_program(mut self, program: &Program) -> String {
+        self.program(program);
+        self.out
+    }
+
+    pub fn print_item(mut self, item: &Item) -> String {
+        self.item(0, item);
+        self.out
+    }
+
+    pub fn print_statement(mut self, statement: &Statement) -> String {
+        self.statement(0, statement);
+        self.out
+    }
+
+    fn program(&mut self, program: &Program) {
+        self.label("Program", None);
+        for item in program.items() {
+            let column = self.child(0);
+            self.item(column, item);
+        }
+    }
+
+    fn item(&mut self, column: usize, item: &Item) {
+        match item {
+            Item::Function {
+                name,
+                arguments,
+                body,
+                span,
+                ..
+            } => {
+                self.label("Function-Item", Some(*span));
+                self.leaf(column, "name", name);
+                self.leaf(column, "parameters", format!("[{}],", arguments.join(", ")));
+                let body_column = self.field(column, "body");
+                self.statement(body_column, body);
+            }
+        }
+    }
+
+    fn statement(&mut self, column: usize, statement: &Statement) {
+        match statement {
+            Statement::Block { body, span, .. } => {
+                self.label("Block", Some(*span));
+                for statement in body {
+                    let statement_column = self.child(column);
+                    self.statement(statement_column, statement);
+                }
+            }
+            Statement::Binding {
+                bind_to,
+                value,
+                span,
+                ..
+            } => {
+                self.label("Let-Stmt", Some(*span));
+                self.leaf(column, "identifier", bind_to);
+                let value_column = self.field(column, "value");
+                self.expression(value_column, value);
+            }
+            Statement::Expression {
+                expression, span, ..
+            } => {
+                self.label("Expression-Stmt", Some(*span));
+                let expression_column = self.field(column, "expression");
+                self.expression(expression_column, expression);
+            }
+            Statement::Return { value, span, .. } => {
+                self.label("Return-Stmt", Some(*span));
+                let value_column = self.field(column, "value");
+                self.expression(value_column, value);
+            }
+            Statement::Branch {
+                condition,
+                then,
+                otherwise,
+                span,
+                ..
+            } => {
+                self.label("If-Stmt", Some(*span));
+                let condition_column = self.field(column, "condition");
+                self.expression(condition_column, condition);
+                let then_column = self.field(column, "then");
+                self.statement(then_column, then);
+                if let Some(otherwise) = otherwise {
+                    let otherwise_column = self.field(column, "else");
+                    self.statement(otherwise_column, otherwise);
+                }
+            }
+            Statement::While {
+                condition,
+                body,
+                span,
+                ..
+            } => {
+                self.label("While-Stmt", Some(*span));
+                let condition_column = self.field(column, "condition");
+                self.expression(condition_column, condition);
+                let body_column = self.field(column, "body");
+                self.statement(body_column, body);
+            }
+            Statement::Error { span, .. } => self.label("Error-Stmt", Some(*span)),
+        }
+    }
+
+    fn expression(&mut self, column: usize, expression: &Expression) {
+        match expression {
+            Expression::Integer { value, span, .. } => {
+                self.label("Int-Expression", Some(*span));
+                self.leaf(column, "value", value);
+            }
+            Expression::Boolean { value, span, .. } => {
+                self.label("Bool-Expression", Some(*span));
+                self.leaf(column, "value", value);
+            }
+            Expression::Reference { name, span, .. } => {
+                self.label("Reference-Expression", Some(*span));
+                self.leaf(column, "name", name);
+            }
+            Expression::Operation {
+                operation, span, ..
+            } => self.operation(column, operation, *span),
+            Expression::Assignment {
+                assign_to,
+                value,
+                span,
+                ..
+            } => {
+                self.label("Assignment-Expression", Some(*span));
+                self.leaf(column, "target", assign_to);
+                let value_column = self.field(column, "value");
+                self.expression(value_column, value);
+            }
+            Expression::Call {
+                name,
+                arguments,
+                span,
+                ..
+            } => {
+                self.label("Call-Expression", Some(*span));
+                self.leaf(column, "name", name);
+                if arguments.is_empty() {
+                    self.leaf(column, "arguments", "[]");
+                } else {
+                    let arguments_column = self.child(column);
+                    self.label("arguments", None);
+                    for argument in arguments {
+                        let argument_column = self.child(arguments_column);
+                        self.expression(argument_column, argument);
+                    }
+                }
+            }
+            Expression::Error { span, .. } => self.label("Error-Expression", Some(*span)),
+        }
+    }
+
+    fn operation(&mut self, column: usize, operation: &Operation, span: Range<usize>) {
+        match operation {
+            Operation::Binary(operation) => {
+                let label = match operation {
+                    BinaryOperation::Add { .. } => "Add-Operation",
+                    BinaryOperation::Sub { .. } => "Sub-Operation",
+                    BinaryOperation::Mul { .. } => "Mul-Operation",
+                    BinaryOperation::Div { .. } => "Div-Operation",
+                    BinaryOperation::Equal { .. } => "Equal-Operation",
+                    BinaryOperation::NotEqual { .. } => "NotEqual-Operation",
+                    BinaryOperation::Less { .. } => "Less-Operation",
+                    BinaryOperation::LessEqual { .. } => "LessEqual-Operation",
+                    BinaryOperation::Greater { .. } => "Greater-Operation",
+                    BinaryOperation::GreaterEqual { .. } => "GreaterEqual-Operation",
+                    BinaryOperation::And { .. } => "And-Operation",
+                    BinaryOperation::Or { .. } => "Or-Operation",
+                };
+                let (lhs, rhs) = operation.operands();
+
+                self.label(label, Some(span));
+                let lhs_column = self.field(column, "lhs");
+                self.expression(lhs_column, lhs);
+                let rhs_column = self.field(column, "rhs");
+                self.expression(rhs_column, rhs);
+            }
+            Operation::Unary(operation) => {
+                let label = match operation {
+                    UnaryOperation::Not(_) => "Not-Operation",
+                    UnaryOperation::Negate(_) => "Negate-Operation",
+                };
+                let operand = operation.operand();
+
+                self.label(label, Some(span));
+                let operand_column = self.field(column, "operand");
+                self.expression(operand_column, operand);
+            }
+        }
+    }
+
+    /// Finishes the current line with a node label.
+    fn label(&mut self, label: &str, span: Option<Range<usize>>) {
+        self.out.push_str(label);
+        if self.spans
+            && let Some(span) = span
+        {
+            let _ = write!(self.out, " @ {}..{}", span.start, span.end);
+        }
+        self.out.push('\n');
+    }
+
+    /// Starts an unnamed child line and returns the column its label starts at.
+    fn child(&mut self, column: usize) -> usize {
+        let _ = write!(self.out, "{:column$}|- ", "");
+        column + 3
+    }
+
+    /// Starts a named child line and returns the column its label starts at.
+    fn field(&mut self, column: usize, name: &str) -> usize {
+        let _ = write!(self.out, "{:column$}|- {name}: ", "");
+        column + 3 + name.len() + 2
+    }
+
+    fn leaf(&mut self, column: usize, name: &str, value: impl Display) {
+        let _ = writeln!(self.out, "{:column$}|- {name}: {value}", "");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn print(source: &str, spans: bool) -> String {
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+        assert!(errors.is_empty(), "`{source}` failed to parse: {errors:?}");
+
+        TreePrinter::new(spans).print_program(&program)
+    }
+
+    #[test]
+    fn prints_statements_and_operations() {
+        let source = "fn f(a, b) { while !a { a = g(1, -b); } if a < 2 { return a * b; } else {} }";
+
+        assert_eq!(
+            print(source, false),
+            "\
+Program
+|- Function-Item
+   |- name: f
+   |- parameters: [a, b],
+   |- body: Block
+            |- While-Stmt
+               |- condition: Not-Operation
+                             |- operand: Reference-Expression
+                                         |- name: a
+               |- body: Block
+                        |- Expression-Stmt
+                           |- expression: Assignment-Expression
+                                          |- target: a
+                                          |- value: Call-Expression
+                                                    |- name: g
+                                                    |- arguments
+                                                       |- Int-Expression
+                                                          |- value: 1
+                                                       |- Negate-Operation
+                                                          |- operand: Reference-Expression
+                                                                      |- name: b
+            |- If-Stmt
+               |- condition: Less-Operation
+                             |- lhs: Reference-Expression
+                                     |- name: a
+                             |- rhs: Int-Expression
+                                     |- value: 2
+               |- then: Block
+                        |- Return-Stmt
+                           |- value: Mul-Operation
+                                     |- lhs: Reference-Expression
+                                             |- name: a
+                                     |- rhs: Reference-Expression
+                                             |- name: b
+               |- else: Block
+"
+        );
+    }
+
+    #[test]
+    fn prints_spans_on_request() {
+        assert_eq!(
+            print("fn f() { return true; }", true),
+            "\
+Program
+|- Function-Item @ 0..23
+   |- name: f
+   |- parameters: [],
+   |- body: Block @ 7..23
+            |- Return-Stmt @ 9..21
+               |- value: Bool-Expression @ 16..20
+                         |- value: true
+"
+        );
+    }
+}
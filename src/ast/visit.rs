@@ -0,0 +1,376 @@
+//! Read-only and mutable traversals of the AST.
+//!
+//! Every `visit_*` method defaults to the matching `walk_*` function, which
+//! visits the children of the node. A pass overrides the nodes it cares about
+//! and calls `walk_*` itself wherever it still wants to descend.
+
+use crate::ast::{
+    BinaryOperation, Expression, Item, Operation, Program, Statement, UnaryOperation,
+};
+
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_item(&mut self, item: &Item) {
+        walk_item(self, item);
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression);
+    }
+
+    fn visit_operation(&mut self, operation: &Operation) {
+        walk_operation(self, operation);
+    }
+
+    fn visit_binary_operation(&mut self, operation: &BinaryOperation) {
+        walk_binary_operation(self, operation);
+    }
+
+    fn visit_unary_operation(&mut self, operation: &UnaryOperation) {
+        walk_unary_operation(self, operation);
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for item in program.items() {
+        visitor.visit_item(item);
+    }
+}
+
+pub fn walk_item<V: Visitor + ?Sized>(visitor: &mut V, item: &Item) {
+    match item {
+        Item::Function { body, .. } => visitor.visit_statement(body),
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::Block { body, .. } => {
+            for statement in body {
+                visitor.visit_statement(statement);
+            }
+        }
+        Statement::Binding { value, .. } => visitor.visit_expression(value),
+        Statement::Expression { expression, .. } => visitor.visit_expression(expression),
+        Statement::Return { value, .. } => visitor.visit_expression(value),
+        Statement::Branch {
+            condition,
+            then,
+            otherwise,
+            ..
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(then);
+            if let Some(otherwise) = otherwise {
+                visitor.visit_statement(otherwise);
+            }
+        }
+        Statement::While {
+            condition, body, ..
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(body);
+        }
+        Statement::Error { .. } => {}
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::Integer { .. }
+        | Expression::Boolean { .. }
+        | Expression::Reference { .. }
+        | Expression::Error { .. } => {}
+        Expression::Operation { operation, .. } => visitor.visit_operation(operation),
+        Expression::Assignment { value, .. } => visitor.visit_expression(value),
+        Expression::Call { arguments, .. } => {
+            for argument in arguments {
+                visitor.visit_expression(argument);
+            }
+        }
+    }
+}
+
+pub fn walk_operation<V: Visitor + ?Sized>(visitor: &mut V, operation: &Operation) {
+    match operation {
+        Operation::Binary(operation) => visitor.visit_binary_operation(operation),
+        Operation::Unary(operation) => visitor.visit_unary_operation(operation),
+    }
+}
+
+pub fn walk_binary_operation<V: Visitor + ?Sized>(visitor: &mut V, operation: &BinaryOperation) {
+    let (lhs, rhs) = operation.operands();
+    visitor.visit_expression(lhs);
+    visitor.visit_expression(rhs);
+}
+
+pub fn walk_unary_operation<V: Visitor + ?Sized>(visitor: &mut V, operation: &UnaryOperation) {
+    visitor.visit_expression(operation.operand());
+}
+
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program);
+    }
+
+    fn visit_item_mut(&mut self, item: &mut Item) {
+        walk_item_mut(self, item);
+    }
+
+    fn visit_statement_mut(&mut self, statement: &mut Statement) {
+        walk_statement_mut(self, statement);
+    }
+
+    fn visit_expression_mut(&mut self, expression: &mut Expression) {
+        walk_expression_mut(self, expression);
+    }
+
+    fn visit_operation_mut(&mut self, operation: &mut Operation) {
+        walk_operation_mut(self, operation);
+    }
+
+    fn visit_binary_operation_mut(&mut self, operation: &mut BinaryOperation) {
+        walk_binary_operation_mut(self, operation);
+    }
+
+    fn visit_unary_operation_mut(&mut self, operation: &mut UnaryOperation) {
+        walk_unary_operation_mut(self, operation);
+    }
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+    for item in program.items_mut() {
+        visitor.visit_item_mut(item);
+    }
+}
+
+pub fn walk_item_mut<V: VisitorMut + ?Sized>(visitor: &mut V, item: &mut Item) {
+    match item {
+        Item::Function { body, .. } => visitor.visit_statement_mut(body),
+    }
+}
+
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statement: &mut Statement) {
+    match statement {
+        Statement::Block { body, .. } => {
+            for statement in body {
+                visitor.visit_statement_mut(statement);
+            }
+        }
+        Statement::Binding { value, .. } => visitor.visit_expression_mut(value),
+        Statement::Expression { expression, .. } => visitor.visit_expression_mut(expression),
+        Statement::Return { value, .. } => visitor.visit_expression_mut(value),
+        Statement::Branch {
+            condition,
+            then,
+            otherwise,
+            ..
+        } => {
+            visitor.visit_expression_mut(condition);
+            visitor.visit_statement_mut(then);
+            if let Some(otherwise) = otherwise {
+                visitor.visit_statement_mut(otherwise);
+            }
+        }
+        Statement::While {
+            condition, body, ..
+        } => {
+            visitor.visit_expression_mut(condition);
+            visitor.visit_statement_mut(body);
+        }
+        Statement::Error { .. } => {}
+    }
+}
+
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut Expression) {
+    match expression {
+        Expression::Integer { .. }
+        | Expression::Boolean { .. }
+        | Expression::Reference { .. }
+        | Expression::Error { .. } => {}
+        Expression::Operation { operation, .. } => visitor.visit_operation_mut(operation),
+        Expression::Assignment { value, .. } => visitor.visit_expression_mut(value),
+        Expression::Call { arguments, .. } => {
+            for argument in arguments {
+                visitor.visit_expression_mut(argument);
+            }
+        }
+    }
+}
+
+pub fn walk_operation_mut<V: VisitorMut + ?Sized>(visitor: &mut V, operation: &mut Operation) {
+    match operation {
+        Operation::Binary(operation) => visitor.visit_binary_operation_mut(operation),
+        Operation::Unary(operation) => visitor.visit_unary_operation_mut(operation),
+    }
+}
+
+pub fn walk_binary_operation_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    operation: &mut BinaryOperation,
+) {
+    let (lhs, rhs) = operation.operands_mut();
+    visitor.visit_expression_mut(lhs);
+    visitor.visit_expression_mut(rhs);
+}
+
+pub fn walk_unary_operation_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    operation: &mut UnaryOperation,
+) {
+    visitor.visit_expression_mut(operation.operand_mut());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::TreePrinter;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use std::collections::BTreeMap;
+
+    fn parse(source: &str) -> Program {
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+        assert!(errors.is_empty(), "`{source}` failed to parse: {errors:?}");
+
+        program
+    }
+
+    /// Counts how often every identifier is declared or used.
+    #[derive(Default)]
+    struct IdentifierCounter {
+        counts: BTreeMap<String, usize>,
+    }
+
+    impl IdentifierCounter {
+        fn count(&mut self, name: &str) {
+            *self.counts.entry(name.to_string()).or_default() += 1;
+        }
+    }
+
+    impl Visitor for IdentifierCounter {
+        fn visit_item(&mut self, item: &Item) {
+            let Item::Function {
+                name, arguments, ..
+            } = item;
+            self.count(name);
+            for argument in arguments {
+                self.count(argument);
+            }
+
+            walk_item(self, item);
+        }
+
+        fn visit_statement(&mut self, statement: &Statement) {
+            if let Statement::Binding { bind_to, .. } = statement {
+                self.count(bind_to);
+            }
+
+            walk_statement(self, statement);
+        }
+
+        fn visit_expression(&mut self, expression: &Expression) {
+            match expression {
+                Expression::Reference { name, .. } | Expression::Call { name, .. } => {
+                    self.count(name)
+                }
+                Expression::Assignment { assign_to, .. } => self.count(assign_to),
+                _ => {}
+            }
+
+            walk_expression(self, expression);
+        }
+    }
+
+    #[test]
+    fn counts_identifiers() {
+        let program = parse(
+            "fn f(a, b) { let c = a + -b; while !(c == 0) { c = c - f(a, b); } return c; } \
+             fn main() { if f(1, 2) > 0 { return 1; } else { return 0; } }",
+        );
+
+        let mut counter = IdentifierCounter::default();
+        counter.visit_program(&program);
+
+        assert_eq!(
+            counter.counts.into_iter().collect::<Vec<_>>(),
+            [
+                ("a".to_string(), 3),
+                ("b".to_string(), 3),
+                ("c".to_string(), 5),
+                ("f".to_string(), 3),
+                ("main".to_string(), 1),
+            ]
+        );
+    }
+
+    /// Counts only the binary operations, relying on the default walk to find
+    /// them wherever they are nested.
+    #[derive(Default)]
+    struct OperationCounter {
+        binary: usize,
+        unary: usize,
+    }
+
+    impl Visitor for OperationCounter {
+        fn visit_binary_operation(&mut self, operation: &BinaryOperation) {
+            self.binary += 1;
+            walk_binary_operation(self, operation);
+        }
+
+        fn visit_unary_operation(&mut self, operation: &UnaryOperation) {
+            self.unary += 1;
+            walk_unary_operation(self, operation);
+        }
+    }
+
+    #[test]
+    fn reaches_nested_operations() {
+        let program = parse("fn f() { g(1 + 2, !(3 < -x)); x = (1 * 2) / 3; }");
+
+        let mut counter = OperationCounter::default();
+        counter.visit_program(&program);
+
+        assert_eq!((counter.binary, counter.unary), (4, 2));
+    }
+
+    struct Renamer<'n> {
+        from: &'n str,
+        to: &'n str,
+    }
+
+    impl VisitorMut for Renamer<'_> {
+        fn visit_expression_mut(&mut self, expression: &mut Expression) {
+            match expression {
+                Expression::Reference { name, .. }
+                | Expression::Assignment {
+                    assign_to: name, ..
+                } if name == self.from => {
+                    *name = self.to.to_string();
+                }
+                _ => {}
+            }
+
+            walk_expression_mut(self, expression);
+        }
+    }
+
+    #[test]
+    fn rewrites_in_place() {
+        let mut program = parse("fn f(x) { x = x + 1; return x; }");
+
+        Renamer { from: "x", to: "y" }.visit_program_mut(&mut program);
+
+        let printed = TreePrinter::new(false).print_program(&program);
+        assert!(printed.contains("|- target: y"));
+        assert!(!printed.contains("name: x"));
+        assert!(printed.contains("|- parameters: [x],"));
+    }
+}
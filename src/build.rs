@@ -0,0 +1,455 @@
+//! Projects and `invariant build`.
+//!
+//! A project is a directory with an `Invariant.toml`. Building parses and
+//! checks every `.inv` file in its source directories and writes the tree of
+//! each to `target/`, in the format the manifest asks for. A fingerprint of
+//! every file that built is kept in `target/`, so that unchanged files are
+//! not built again.
+
+use crate::check::{CheckError, check};
+use crate::lexer::Lexer;
+use crate::manifest::{self, ArtifactFormat, Manifest, ManifestError};
+use crate::parser::{ParseError, Parser};
+use crate::serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The directory artifacts are written to, relative to the project root.
+pub const TARGET_DIRECTORY: &str = "target";
+
+/// The file in the target directory that records the fingerprint of every
+/// built source.
+const FINGERPRINTS: &str = ".fingerprints";
+
+const MAIN: &str = "fn main() {\n    print(42);\n}\n";
+
+#[derive(Debug)]
+pub enum ProjectError {
+    /// No `Invariant.toml` in the directory or any of its parents.
+    NotFound,
+    /// The directory of a new project exists already.
+    Exists(PathBuf),
+    InvalidName(String),
+    Io(PathBuf, io::Error),
+    Manifest(PathBuf, ManifestError),
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectError::NotFound => write!(
+                f,
+                "could not find `{}` in this directory or any parent",
+                manifest::FILE_NAME
+            ),
+            ProjectError::Exists(path) => write!(f, "`{}` already exists", path.display()),
+            ProjectError::InvalidName(name) => write!(
+                f,
+                "`{name}` is not a valid package name; names start with a letter and \
+                 continue with letters, digits, `-` and `_`"
+            ),
+            ProjectError::Io(path, error) => write!(f, "`{}`: {error}", path.display()),
+            ProjectError::Manifest(path, error) => write!(f, "`{}`: {error}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {}
+
+/// Why a source file did not build.
+#[derive(Debug)]
+pub enum Failure {
+    Parse(Vec<ParseError>),
+    Check(Vec<CheckError>),
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    /// The file did not change since it was last built.
+    Fresh,
+    /// The file was built into the artifact at this path.
+    Built(PathBuf),
+    Failed(Failure),
+}
+
+/// A source file and what building it did.
+#[derive(Debug)]
+pub struct Unit {
+    /// The path of the file, relative to the project root.
+    pub source: PathBuf,
+    pub text: String,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug)]
+pub struct Project {
+    /// The directory of the manifest. Paths of the project are joined onto
+    /// it, so they stay relative if it is.
+    pub root: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl Project {
+    pub fn load(root: impl Into<PathBuf>) -> Result<Self, ProjectError> {
+        let root = root.into();
+        let path = root.join(manifest::FILE_NAME);
+        let text =
+            fs::read_to_string(&path).map_err(|error| ProjectError::Io(path.clone(), error))?;
+        let manifest =
+            Manifest::parse(&text).map_err(|error| ProjectError::Manifest(path, error))?;
+
+        Ok(Self { root, manifest })
+    }
+
+    /// Loads the project whose manifest is in `start` or the closest of its
+    /// parents. The root is `start` followed by one `..` per level, so it is
+    /// relative if `start` is; an empty `start` is the current directory.
+    pub fn discover(start: &Path) -> Result<Self, ProjectError> {
+        let mut directory = start.to_path_buf();
+
+        loop {
+            if directory.join(manifest::FILE_NAME).is_file() {
+                return Project::load(directory);
+            }
+
+            let resolved = if directory.as_os_str().is_empty() {
+                Path::new(".").canonicalize()
+            } else {
+                directory.canonicalize()
+            };
+            match resolved {
+                Ok(resolved) if resolved.parent().is_some() => directory.push(".."),
+                Ok(_) => return Err(ProjectError::NotFound),
+                Err(error) => return Err(ProjectError::Io(directory, error)),
+            }
+        }
+    }
+
+    /// Creates the project `name` in a new directory of that name inside
+    /// `parent`, with a manifest and a `src/main.inv` that prints a number.
+    pub fn create(parent: &Path, name: &str) -> Result<Self, ProjectError> {
+        if !manifest::is_valid_name(name) {
+            return Err(ProjectError::InvalidName(name.to_string()));
+        }
+        let root = parent.join(name);
+        if root.exists() {
+            return Err(ProjectError::Exists(root));
+        }
+
+        let manifest = Manifest::new(name);
+        let write = |path: &Path, contents: &str| {
+            let path = root.join(path);
+            path.parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| fs::write(&path, contents))
+                .map_err(|error| ProjectError::Io(path, error))
+        };
+        write(Path::new(manifest::FILE_NAME), &manifest.to_toml())?;
+        write(&manifest.entry, MAIN)?;
+        write(Path::new(".gitignore"), &format!("/{TARGET_DIRECTORY}\n"))?;
+
+        Ok(Self { root, manifest })
+    }
+
+    pub fn target_directory(&self) -> PathBuf {
+        self.root.join(TARGET_DIRECTORY)
+    }
+
+    /// The entry file and every `.inv` file in the source directories,
+    /// relative to the root, each once and in a stable order.
+    pub fn sources(&self) -> Result<Vec<PathBuf>, ProjectError> {
+        let mut sources = Vec::new();
+        for directory in &self.manifest.sources {
+            collect_sources(&self.root, directory, &mut sources)?;
+        }
+        // directories may overlap, like `src` and `src/nested`
+        sources.sort();
+        sources.dedup();
+        if !sources.contains(&self.manifest.entry) {
+            sources.insert(0, self.manifest.entry.clone());
+        }
+
+        Ok(sources)
+    }
+
+    /// The artifact `source` builds into.
+    pub fn artifact(&self, source: &Path) -> PathBuf {
+        self.target_directory()
+            .join(source.with_extension(self.manifest.format.extension()))
+    }
+
+    /// Builds every source whose fingerprint changed or whose artifact is
+    /// missing. Sources that fail lose their artifact from an earlier build
+    /// and are left out of the record of fingerprints, so they are built
+    /// again next time.
+    pub fn build(&self) -> Result<Vec<Unit>, ProjectError> {
+        let target = self.target_directory();
+        let record = target.join(FINGERPRINTS);
+        let previous = read_fingerprints(&record);
+        let mut current = Vec::new();
+        let mut units = Vec::new();
+
+        for source in self.sources()? {
+            let path = self.root.join(&source);
+            let text = fs::read_to_string(&path).map_err(|error| ProjectError::Io(path, error))?;
+            let fingerprint = fingerprint(&self.manifest, &text);
+            let artifact = self.artifact(&source);
+
+            let outcome = if previous.get(&source) == Some(&fingerprint) && artifact.is_file() {
+                Outcome::Fresh
+            } else {
+                match compile(&self.manifest, &text) {
+                    Ok(compiled) => {
+                        let written = artifact
+                            .parent()
+                            .map_or(Ok(()), fs::create_dir_all)
+                            .and_then(|()| fs::write(&artifact, compiled));
+                        written.map_err(|error| ProjectError::Io(artifact.clone(), error))?;
+                        Outcome::Built(artifact)
+                    }
+                    Err(failure) => {
+                        match fs::remove_file(&artifact) {
+                            Err(error) if error.kind() != io::ErrorKind::NotFound => {
+                                return Err(ProjectError::Io(artifact, error));
+                            }
+                            _ => {}
+                        }
+                        Outcome::Failed(failure)
+                    }
+                }
+            };
+
+            if !matches!(outcome, Outcome::Failed(_)) {
+                current.push((source.clone(), fingerprint));
+            }
+            units.push(Unit {
+                source,
+                text,
+                outcome,
+            });
+        }
+
+        write_fingerprints(&record, &current).map_err(|error| ProjectError::Io(record, error))?;
+
+        Ok(units)
+    }
+}
+
+fn collect_sources(
+    root: &Path,
+    directory: &Path,
+    sources: &mut Vec<PathBuf>,
+) -> Result<(), ProjectError> {
+    let path = root.join(directory);
+    let entries = fs::read_dir(&path).map_err(|error| ProjectError::Io(path.clone(), error))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|error| ProjectError::Io(path.clone(), error))?;
+        let relative = directory.join(entry.file_name());
+        let is_directory = entry
+            .file_type()
+            .map_err(|error| ProjectError::Io(path.clone(), error))?
+            .is_dir();
+
+        if is_directory {
+            collect_sources(root, &relative, sources)?;
+        } else if relative
+            .extension()
+            .is_some_and(|extension| extension == "inv")
+        {
+            sources.push(relative);
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses, checks and serializes a source file.
+fn compile(manifest: &Manifest, text: &str) -> Result<String, Failure> {
+    let (program, errors) = Parser::new(Lexer::new(text)).parse();
+    if !errors.is_empty() {
+        return Err(Failure::Parse(errors));
+    }
+    let (_, errors) = check(&program);
+    if !errors.is_empty() {
+        return Err(Failure::Check(errors));
+    }
+
+    Ok(match manifest.format {
+        ArtifactFormat::Json => format!("{:#}\n", serialize::program_to_json(&program)),
+        ArtifactFormat::Sexp => serialize::program_to_sexp(&program),
+    })
+}
+
+/// A 64-bit FNV-1a hash of everything an artifact depends on: the source,
+/// the artifact format and the version of this crate. Unlike `std`'s
+/// hashers, its value is stable across builds of the tool.
+fn fingerprint(manifest: &Manifest, text: &str) -> u64 {
+    let inputs = [env!("CARGO_PKG_VERSION"), manifest.format.name(), text];
+
+    inputs
+        .iter()
+        .flat_map(|input| input.bytes().chain([0]))
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// Reads the fingerprints of the last build. A missing or damaged record only
+/// means that everything is built again.
+fn read_fingerprints(path: &Path) -> HashMap<PathBuf, u64> {
+    let Ok(text) = fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+
+    text.lines()
+        .filter_map(|line| {
+            let (fingerprint, source) = line.split_once(' ')?;
+            let fingerprint = u64::from_str_radix(fingerprint, 16).ok()?;
+
+            Some((PathBuf::from(source), fingerprint))
+        })
+        .collect()
+}
+
+fn write_fingerprints(path: &Path, fingerprints: &[(PathBuf, u64)]) -> io::Result<()> {
+    let mut text = String::new();
+    for (source, fingerprint) in fingerprints {
+        text.push_str(&format!("{fingerprint:016x} {}\n", source.display()));
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for one test, removed when dropped.
+    struct TemporaryDirectory(PathBuf);
+
+    impl TemporaryDirectory {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("invariant-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+
+            Self(path)
+        }
+    }
+
+    impl Drop for TemporaryDirectory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn outcomes(project: &Project) -> Vec<String> {
+        project
+            .build()
+            .unwrap()
+            .iter()
+            .map(|unit| {
+                let outcome = match &unit.outcome {
+                    Outcome::Fresh => "fresh",
+                    Outcome::Built(_) => "built",
+                    Outcome::Failed(Failure::Parse(_)) => "parse error",
+                    Outcome::Failed(Failure::Check(_)) => "check error",
+                };
+                format!("{}: {outcome}", unit.source.display())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn creates_and_discovers_projects() {
+        let directory = TemporaryDirectory::new("create");
+
+        let project = Project::create(&directory.0, "hello").unwrap();
+        assert!(matches!(
+            Project::create(&directory.0, "hello"),
+            Err(ProjectError::Exists(_))
+        ));
+        assert!(matches!(
+            Project::create(&directory.0, "no spaces"),
+            Err(ProjectError::InvalidName(_))
+        ));
+
+        let discovered = Project::discover(&project.root.join("src")).unwrap();
+        assert_eq!(discovered.root, project.root.join("src").join(".."));
+        assert_eq!(discovered.manifest, Manifest::new("hello"));
+        assert_eq!(
+            fs::read_to_string(project.root.join("src/main.inv")).unwrap(),
+            MAIN
+        );
+    }
+
+    #[test]
+    fn rebuilds_only_what_changed() {
+        let directory = TemporaryDirectory::new("build");
+        let project = Project::create(&directory.0, "cached").unwrap();
+        let library = project.root.join("src/nested/library.inv");
+        fs::create_dir_all(library.parent().unwrap()).unwrap();
+        fs::write(&library, "fn f() { return 1; }").unwrap();
+
+        assert_eq!(
+            outcomes(&project),
+            ["src/main.inv: built", "src/nested/library.inv: built"]
+        );
+        assert!(
+            project
+                .target_directory()
+                .join("src/nested/library.ast.json")
+                .is_file()
+        );
+        assert_eq!(
+            outcomes(&project),
+            ["src/main.inv: fresh", "src/nested/library.inv: fresh"]
+        );
+
+        fs::write(&library, "fn f() { return g(); }").unwrap();
+        assert_eq!(
+            outcomes(&project),
+            ["src/main.inv: fresh", "src/nested/library.inv: check error"]
+        );
+        assert!(
+            !project
+                .artifact(Path::new("src/nested/library.inv"))
+                .exists()
+        );
+        assert_eq!(
+            outcomes(&project),
+            ["src/main.inv: fresh", "src/nested/library.inv: check error"]
+        );
+
+        fs::write(&library, "fn f() { return 2; }").unwrap();
+        fs::remove_file(project.artifact(Path::new("src/main.inv"))).unwrap();
+        assert_eq!(
+            outcomes(&project),
+            ["src/main.inv: built", "src/nested/library.inv: built"]
+        );
+    }
+
+    #[test]
+    fn builds_overlapping_sources_once() {
+        let directory = TemporaryDirectory::new("overlap");
+        let mut project = Project::create(&directory.0, "overlap").unwrap();
+        fs::create_dir_all(project.root.join("src/nested")).unwrap();
+        fs::write(project.root.join("src/nested/f.inv"), "fn f() {}").unwrap();
+        project.manifest =
+            Manifest::parse("[package]\nname = \"overlap\"\n[build]\nsources = [\"src\", \"./src\", \"src/nested\"]")
+                .unwrap();
+
+        assert_eq!(
+            outcomes(&project),
+            ["src/main.inv: built", "src/nested/f.inv: built"]
+        );
+    }
+}
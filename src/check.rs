@@ -0,0 +1,457 @@
+//! Name resolution. Every variable reference and assignment is linked to the
+//! parameter or `let` that declares it, and every call to the function it
+//! calls. The results are side tables keyed by the node ID of the reference.
+//!
+//! Variables are visible from the statement after their `let` to the end of
+//! the enclosing block, and a `let` may shadow an earlier variable of the
+//! same name. Functions are visible in the whole program, regardless of
+//! their order.
+
+use crate::ast::id::{NodeId, NodeMap};
+use crate::ast::visit::{Visitor, walk_expression, walk_statement};
+use crate::ast::{Expression, Item, Program, Statement};
+use std::collections::HashMap;
+use std::fmt;
+use std::range::Range;
+
+/// A function provided by the language rather than the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Builtin {
+    /// `print(value)` writes the value and a line break to the output.
+    Print,
+}
+
+impl Builtin {
+    pub const ALL: [Builtin; 1] = [Builtin::Print];
+
+    pub fn lookup(name: &str) -> Option<Builtin> {
+        Builtin::ALL
+            .into_iter()
+            .find(|builtin| builtin.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Builtin::Print => "print",
+        }
+    }
+
+    pub fn arity(self) -> usize {
+        match self {
+            Builtin::Print => 1,
+        }
+    }
+}
+
+/// Where a variable is declared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Definition {
+    /// The `index`th parameter of the function `function`.
+    Parameter { function: NodeId, index: usize },
+    /// The `let` statement with this ID.
+    Local(NodeId),
+}
+
+/// What a call calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Callee {
+    /// The function item with this ID.
+    Function(NodeId),
+    Builtin(Builtin),
+}
+
+#[derive(Debug, Default)]
+pub struct Resolution {
+    /// The declaration of every `Reference` and `Assignment` expression.
+    pub definitions: NodeMap<Definition>,
+    /// The target of every `Call` expression.
+    pub callees: NodeMap<Callee>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckError {
+    /// A second function with the same name. Carries the spans of the
+    /// headers of both.
+    DuplicateFunction {
+        name: String,
+        span: Range<usize>,
+        previous: Range<usize>,
+    },
+    /// A function whose parameter list repeats a name. Carries the span of
+    /// the function header.
+    DuplicateParameter {
+        name: String,
+        span: Range<usize>,
+    },
+    UndefinedVariable {
+        name: String,
+        span: Range<usize>,
+    },
+    UndefinedFunction {
+        name: String,
+        span: Range<usize>,
+    },
+    /// A call with the wrong number of arguments, together with the span of
+    /// the called function's header unless it is a builtin.
+    ArityMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+        span: Range<usize>,
+        definition: Option<Range<usize>>,
+    },
+}
+
+impl CheckError {
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            CheckError::DuplicateFunction { span, .. }
+            | CheckError::DuplicateParameter { span, .. }
+            | CheckError::UndefinedVariable { span, .. }
+            | CheckError::UndefinedFunction { span, .. }
+            | CheckError::ArityMismatch { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckError::DuplicateFunction { name, .. } => {
+                write!(f, "the function `{name}` is defined more than once")
+            }
+            CheckError::DuplicateParameter { name, .. } => {
+                write!(f, "the parameter `{name}` is declared more than once")
+            }
+            CheckError::UndefinedVariable { name, .. } => {
+                write!(f, "cannot find variable `{name}` in this scope")
+            }
+            CheckError::UndefinedFunction { name, .. } => {
+                write!(f, "cannot find function `{name}`")
+            }
+            CheckError::ArityMismatch {
+                name,
+                expected,
+                found,
+                ..
+            } => {
+                let plural = |count: &usize| if *count == 1 { "" } else { "s" };
+
+                write!(
+                    f,
+                    "`{name}` takes {expected} argument{} but {found} argument{} supplied",
+                    plural(expected),
+                    if *found == 1 { " was" } else { "s were" }
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CheckError {}
+
+/// Resolves every name in `program`. Names that cannot be resolved are
+/// reported and left out of the `Resolution`.
+pub fn check(program: &Program) -> (Resolution, Vec<CheckError>) {
+    let mut resolver = Resolver::default();
+
+    for item in program.items() {
+        let Item::Function {
+            name,
+            arguments,
+            id,
+            ..
+        } = item;
+        let header = header(item);
+
+        if let Some(previous) = resolver.functions.get(name.as_str()) {
+            resolver.errors.push(CheckError::DuplicateFunction {
+                name: name.clone(),
+                span: header,
+                previous: previous.header,
+            });
+            continue;
+        }
+
+        resolver.functions.insert(
+            name.clone(),
+            Signature {
+                id: *id,
+                arity: arguments.len(),
+                header,
+            },
+        );
+    }
+
+    resolver.visit_program(program);
+
+    (resolver.resolution, resolver.errors)
+}
+
+/// The span of a function up to the opening brace of its body.
+fn header(item: &Item) -> Range<usize> {
+    let Item::Function { body, span, .. } = item;
+
+    Range::from(span.start..body.span().start)
+}
+
+#[derive(Debug)]
+struct Signature {
+    id: NodeId,
+    arity: usize,
+    header: Range<usize>,
+}
+
+#[derive(Debug, Default)]
+struct Resolver {
+    functions: HashMap<String, Signature>,
+    /// The variables in scope, innermost block last.
+    scopes: Vec<HashMap<String, Definition>>,
+    resolution: Resolution,
+    errors: Vec<CheckError>,
+}
+
+impl Resolver {
+    fn lookup(&self, name: &str) -> Option<Definition> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    fn resolve_variable(&mut self, name: &str, id: NodeId, span: Range<usize>) {
+        match self.lookup(name) {
+            Some(definition) => {
+                self.resolution.definitions.insert(id, definition);
+            }
+            None => self.errors.push(CheckError::UndefinedVariable {
+                name: name.to_string(),
+                span,
+            }),
+        }
+    }
+
+    fn resolve_call(&mut self, name: &str, found: usize, id: NodeId, span: Range<usize>) {
+        let (callee, expected, definition) = match self.functions.get(name) {
+            Some(signature) => (
+                Callee::Function(signature.id),
+                signature.arity,
+                Some(signature.header),
+            ),
+            None => match Builtin::lookup(name) {
+                Some(builtin) => (Callee::Builtin(builtin), builtin.arity(), None),
+                None => {
+                    self.errors.push(CheckError::UndefinedFunction {
+                        name: name.to_string(),
+                        span,
+                    });
+                    return;
+                }
+            },
+        };
+
+        if expected != found {
+            self.errors.push(CheckError::ArityMismatch {
+                name: name.to_string(),
+                expected,
+                found,
+                span,
+                definition,
+            });
+        }
+        self.resolution.callees.insert(id, callee);
+    }
+}
+
+impl Visitor for Resolver {
+    fn visit_item(&mut self, item: &Item) {
+        let Item::Function {
+            name,
+            arguments,
+            body,
+            id,
+            ..
+        } = item;
+
+        // a duplicate function is not resolved, so its body is not checked
+        // against the wrong signature
+        if self
+            .functions
+            .get(name)
+            .is_none_or(|signature| signature.id != *id)
+        {
+            return;
+        }
+
+        let mut parameters = HashMap::new();
+        for (index, argument) in arguments.iter().enumerate() {
+            let definition = Definition::Parameter {
+                function: *id,
+                index,
+            };
+            if parameters.insert(argument.clone(), definition).is_some() {
+                self.errors.push(CheckError::DuplicateParameter {
+                    name: argument.clone(),
+                    span: header(item),
+                });
+            }
+        }
+
+        self.scopes.push(parameters);
+        self.visit_statement(body);
+        self.scopes.pop();
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Block { .. } => {
+                self.scopes.push(HashMap::new());
+                walk_statement(self, statement);
+                self.scopes.pop();
+            }
+            Statement::Binding {
+                bind_to, value, id, ..
+            } => {
+                // the value is resolved first, so `let x = x + 1;` refers to
+                // the outer `x`
+                self.visit_expression(value);
+                self.scopes
+                    .last_mut()
+                    .expect("statements are always inside a block")
+                    .insert(bind_to.clone(), Definition::Local(*id));
+            }
+            _ => walk_statement(self, statement),
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Reference { name, span, id } => self.resolve_variable(name, *id, *span),
+            Expression::Assignment {
+                assign_to,
+                span,
+                id,
+                ..
+            } => {
+                self.resolve_variable(assign_to, *id, *span);
+                walk_expression(self, expression);
+            }
+            Expression::Call {
+                name,
+                arguments,
+                span,
+                id,
+            } => {
+                self.resolve_call(name, arguments.len(), *id, *span);
+                walk_expression(self, expression);
+            }
+            _ => walk_expression(self, expression),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn parse(source: &str) -> Program {
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+        assert!(errors.is_empty(), "`{source}` failed to parse: {errors:?}");
+
+        program
+    }
+
+    fn errors(source: &str) -> Vec<String> {
+        check(&parse(source))
+            .1
+            .iter()
+            .map(|error| format!("{}: {error}", &source[error.span()]))
+            .collect()
+    }
+
+    #[test]
+    fn resolves_parameters_locals_and_calls() {
+        let program = parse(
+            "fn f(a) { let b = a; { let a = b; b = a; } return g(a, b); } fn g(x, y) { print(x); }",
+        );
+        let (resolution, errors) = check(&program);
+        assert_eq!(errors, []);
+
+        let [f, g] = program.items() else {
+            panic!("expected two functions");
+        };
+        let Item::Function { body, .. } = f;
+        let Statement::Block { body, .. } = body else {
+            panic!("function bodies are blocks");
+        };
+        let [
+            Statement::Binding {
+                value: a, id: b, ..
+            },
+            Statement::Block { body: inner, .. },
+            Statement::Return { value: call, .. },
+        ] = body.as_slice()
+        else {
+            panic!("unexpected statements");
+        };
+        let [
+            Statement::Binding { id: inner_a, .. },
+            Statement::Expression {
+                expression: assignment,
+                ..
+            },
+        ] = inner.as_slice()
+        else {
+            panic!("unexpected inner statements");
+        };
+
+        assert_eq!(
+            resolution.definitions[a.id()],
+            Definition::Parameter {
+                function: f.id(),
+                index: 0
+            }
+        );
+        assert_eq!(
+            resolution.definitions[assignment.id()],
+            Definition::Local(*b)
+        );
+        let Expression::Assignment { value, .. } = assignment else {
+            panic!("expected an assignment");
+        };
+        assert_eq!(
+            resolution.definitions[value.id()],
+            Definition::Local(*inner_a)
+        );
+        assert_eq!(resolution.callees[call.id()], Callee::Function(g.id()));
+        assert_eq!(resolution.callees.len(), 2);
+    }
+
+    #[test]
+    fn bindings_are_scoped_to_their_block() {
+        assert_eq!(
+            errors("fn f() { let x = x; if true { let y = 1; } return y; }"),
+            [
+                "x: cannot find variable `x` in this scope",
+                "y: cannot find variable `y` in this scope"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_undefined_and_mismatched_calls() {
+        assert_eq!(
+            errors("fn f(a) { g(); f(); print(1, 2); z = 1; } fn f() {} fn h(a, a) {}"),
+            [
+                "fn f() : the function `f` is defined more than once",
+                "g(): cannot find function `g`",
+                "f(): `f` takes 1 argument but 0 arguments were supplied",
+                "print(1, 2): `print` takes 1 argument but 2 arguments were supplied",
+                "z = 1: cannot find variable `z` in this scope",
+                "fn h(a, a) : the parameter `a` is declared more than once",
+            ]
+        );
+    }
+}
@@ -0,0 +1,532 @@
+use crate::check::CheckError;
+use crate::interpret::RuntimeError;
+use crate::parser::ParseError;
+use crate::token::TokenKind;
+use std::fmt::Write;
+use std::io::IsTerminal;
+use std::range::Range;
+
+/// A source file together with the byte offsets of its line starts, so that
+/// spans can be mapped back to lines and columns.
+pub struct Source<'s> {
+    name: &'s str,
+    text: &'s str,
+    line_starts: Vec<usize>,
+}
+
+/// One-based line and column of a byte offset. Columns count characters,
+/// not bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl<'s> Source<'s> {
+    pub fn new(name: &'s str, text: &'s str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        Self {
+            name,
+            text,
+            line_starts,
+        }
+    }
+
+    /// Span of the (empty) position right behind the last character.
+    pub fn end(&self) -> Range<usize> {
+        Range::from(self.text.len()..self.text.len())
+    }
+
+    pub fn location(&self, offset: usize) -> Location {
+        let offset = offset.min(self.text.len());
+        let line = self.line_index(offset);
+        let column = self.text[self.line_starts[line]..offset].chars().count() + 1;
+
+        Location {
+            line: line + 1,
+            column,
+        }
+    }
+
+    fn line_index(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        }
+    }
+
+    /// Text of the zero-based `line` without its line terminator.
+    fn line(&self, line: usize) -> &'s str {
+        let start = self.line_starts[line];
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.text.len(), |&next| next - 1);
+
+        self.text[start..end].trim_end_matches('\r')
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    span: Range<usize>,
+    message: Option<String>,
+    primary: bool,
+}
+
+impl Label {
+    pub fn primary(span: Range<usize>) -> Self {
+        Self {
+            span,
+            message: None,
+            primary: true,
+        }
+    }
+
+    pub fn secondary(span: Range<usize>) -> Self {
+        Self {
+            span,
+            message: None,
+            primary: false,
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    severity: Severity,
+    message: String,
+    labels: Vec<Label>,
+    notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    pub fn with_label(mut self, label: Label) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn from_parse_error(error: &ParseError, source: &Source) -> Self {
+        let diagnostic = Diagnostic::error(error.to_string());
+
+        match error {
+            ParseError::UnexpectedToken(_, span, expected) => {
+                let label = match expected {
+                    Some(expected) => format!("expected {expected}"),
+                    None => "unexpected token".to_string(),
+                };
+
+                diagnostic.with_label(Label::primary(*span).with_message(label))
+            }
+            ParseError::UnexpectedEof(expected) => diagnostic.with_label(
+                Label::primary(source.end()).with_message(format!("expected {expected}")),
+            ),
+            ParseError::UnterminatedBlock(opened, expected_at) => diagnostic
+                .with_label(
+                    Label::primary(*expected_at)
+                        .with_message(format!("expected {}", TokenKind::RBrace)),
+                )
+                .with_label(Label::secondary(*opened).with_message("block opened here")),
+            ParseError::InvalidLiteral(_, span) => diagnostic
+                .with_label(Label::primary(*span).with_message("invalid literal"))
+                .with_note(
+                    "integer literals are decimal or start with `0x`, `0o` or `0b`, \
+                     and may separate digits with single underscores",
+                ),
+            ParseError::IntegerOverflow(_, span) => diagnostic
+                .with_label(Label::primary(*span).with_message("does not fit into 64 bits"))
+                .with_note(format!(
+                    "integers range from `{}` to `{}`",
+                    i64::MIN,
+                    i64::MAX
+                )),
+            ParseError::UnterminatedComment(span) => {
+                let opening = Range::from(span.start..span.start + 2);
+
+                diagnostic
+                    .with_label(Label::primary(opening).with_message("comment opened here"))
+                    .with_note("block comments nest, so every `/*` needs its own `*/`")
+            }
+            ParseError::UnexpectedCharacter(_, span) => {
+                diagnostic.with_label(Label::primary(*span).with_message("not a valid token"))
+            }
+            ParseError::InvalidAssignmentTarget(span) => diagnostic
+                .with_label(Label::primary(*span).with_message("cannot assign to this"))
+                .with_note("only variables can be assigned to"),
+        }
+    }
+
+    pub fn from_check_error(error: &CheckError) -> Self {
+        let diagnostic = Diagnostic::error(error.to_string());
+
+        match error {
+            CheckError::DuplicateFunction { span, previous, .. } => diagnostic
+                .with_label(Label::primary(*span).with_message("redefined here"))
+                .with_label(Label::secondary(*previous).with_message("first defined here")),
+            CheckError::DuplicateParameter { span, .. } => {
+                diagnostic.with_label(Label::primary(*span).with_message("in this function"))
+            }
+            CheckError::UndefinedVariable { span, .. } => {
+                diagnostic.with_label(Label::primary(*span).with_message("not declared"))
+            }
+            CheckError::UndefinedFunction { span, .. } => {
+                diagnostic.with_label(Label::primary(*span).with_message("not defined"))
+            }
+            CheckError::ArityMismatch {
+                expected,
+                span,
+                definition,
+                ..
+            } => {
+                let diagnostic = diagnostic
+                    .with_label(Label::primary(*span).with_message(format!("expected {expected}")));
+
+                match definition {
+                    Some(definition) => diagnostic
+                        .with_label(Label::secondary(*definition).with_message("defined here")),
+                    None => diagnostic,
+                }
+            }
+        }
+    }
+
+    pub fn from_runtime_error(error: &RuntimeError) -> Self {
+        let diagnostic = Diagnostic::error(error.to_string());
+
+        match error.span() {
+            Some(span) => diagnostic.with_label(Label::primary(span)),
+            None => diagnostic,
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Renders diagnostics the way rustc does: a header, the offending source
+/// lines with underlined labels, and trailing notes.
+pub struct Renderer {
+    colored: bool,
+}
+
+impl Renderer {
+    pub fn new(colored: bool) -> Self {
+        Self { colored }
+    }
+
+    /// A renderer that only uses colours when stdout is a terminal.
+    pub fn stdout() -> Self {
+        Self::new(std::io::stdout().is_terminal())
+    }
+
+    /// A renderer that only uses colours when stderr is a terminal.
+    pub fn stderr() -> Self {
+        Self::new(std::io::stderr().is_terminal())
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic, source: &Source) -> String {
+        let mut out = String::new();
+
+        let (severity, severity_color) = match diagnostic.severity {
+            Severity::Error => ("error", RED),
+            Severity::Warning => ("warning", YELLOW),
+        };
+        let _ = writeln!(
+            out,
+            "{}{severity}{}{}: {}{}",
+            self.paint(severity_color),
+            self.paint(RESET),
+            self.paint(BOLD),
+            diagnostic.message,
+            self.paint(RESET),
+        );
+
+        let mut labels = diagnostic
+            .labels
+            .iter()
+            .map(|label| (source.location(label.span.start), label))
+            .collect::<Vec<_>>();
+        labels.sort_by_key(|(location, label)| (location.line, !label.primary, location.column));
+
+        let gutter_width = labels
+            .iter()
+            .map(|(location, _)| location.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let gutter = " ".repeat(gutter_width);
+
+        let primary = labels
+            .iter()
+            .find(|(_, label)| label.primary)
+            .or(labels.first());
+        if let Some((location, _)) = primary {
+            let _ = writeln!(
+                out,
+                "{gutter}{}-->{} {}:{}:{}",
+                self.paint(BLUE),
+                self.paint(RESET),
+                source.name,
+                location.line,
+                location.column,
+            );
+        }
+
+        if !labels.is_empty() {
+            let _ = writeln!(out, "{gutter} {}|{}", self.paint(BLUE), self.paint(RESET));
+        }
+
+        let mut previous_line = None;
+        for (location, label) in &labels {
+            if previous_line != Some(location.line) {
+                if let Some(previous_line) = previous_line
+                    && location.line > previous_line + 1
+                {
+                    let _ = writeln!(out, "{}...{}", self.paint(BLUE), self.paint(RESET));
+                }
+
+                let _ = writeln!(
+                    out,
+                    "{}{:>gutter_width$} |{} {}",
+                    self.paint(BLUE),
+                    location.line,
+                    self.paint(RESET),
+                    source.line(location.line - 1),
+                );
+                out.truncate(out.trim_end().len());
+                out.push('\n');
+                previous_line = Some(location.line);
+            }
+
+            self.render_label(&mut out, &gutter, *location, label, source);
+        }
+
+        if !diagnostic.notes.is_empty() && !labels.is_empty() {
+            let _ = writeln!(out, "{gutter} {}|{}", self.paint(BLUE), self.paint(RESET));
+        }
+
+        for note in &diagnostic.notes {
+            let _ = writeln!(
+                out,
+                "{gutter} {}={} {}note{}: {note}",
+                self.paint(BLUE),
+                self.paint(RESET),
+                self.paint(BOLD),
+                self.paint(RESET),
+            );
+        }
+
+        out
+    }
+
+    fn render_label(
+        &self,
+        out: &mut String,
+        gutter: &str,
+        location: Location,
+        label: &Label,
+        source: &Source,
+    ) {
+        let line = source.line(location.line - 1);
+        let prefix = line
+            .chars()
+            .take(location.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        // spans running over several lines are only underlined up to the end
+        // of their first line
+        let line_end = source.line_starts[location.line - 1] + line.len();
+        let start = label.span.start.min(line_end);
+        let end = label.span.end.max(start).min(line_end);
+        let width = source.text[start..end].chars().count().max(1);
+
+        let (marker, color) = if label.primary {
+            ("^", RED)
+        } else {
+            ("-", BLUE)
+        };
+        let message = label
+            .message
+            .as_deref()
+            .map(|message| format!(" {message}"))
+            .unwrap_or_default();
+
+        let _ = writeln!(
+            out,
+            "{gutter} {}|{} {prefix}{}{}{message}{}",
+            self.paint(BLUE),
+            self.paint(RESET),
+            self.paint(color),
+            marker.repeat(width),
+            self.paint(RESET),
+        );
+    }
+
+    fn paint(&self, code: &'static str) -> &'static str {
+        if self.colored { code } else { "" }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    #[test]
+    fn maps_offsets_to_lines_and_columns() {
+        let source = Source::new("test.inv", "fn main() {\r\n    let ä = 1;\n}");
+        let cases = [
+            (0, (1, 1)),
+            (3, (1, 4)),
+            (11, (1, 12)),
+            (13, (2, 1)),
+            (17, (2, 5)),
+            (21, (2, 9)),
+            (23, (2, 10)),
+            (29, (3, 1)),
+            (30, (3, 2)),
+        ];
+
+        for (offset, (line, column)) in cases {
+            assert_eq!(
+                source.location(offset),
+                Location { line, column },
+                "at offset {offset}"
+            );
+        }
+        assert_eq!(source.line(1), "    let ä = 1;");
+    }
+
+    #[test]
+    fn renders_labels_and_notes() {
+        let text = "fn main() {\n    let x = 1;\n    1 + 2 = x;\n}";
+        let source = Source::new("test.inv", text);
+        let diagnostic = Diagnostic::error("invalid assignment target")
+            .with_label(Label::primary(Range::from(31..36)).with_message("cannot assign to this"))
+            .with_label(Label::secondary(Range::from(20..21)).with_message("`x` is declared here"))
+            .with_note("only variables can be assigned to");
+
+        let rendered = Renderer::new(false).render(&diagnostic, &source);
+        assert_eq!(
+            rendered,
+            "\
+error: invalid assignment target
+ --> test.inv:3:5
+  |
+2 |     let x = 1;
+  |         - `x` is declared here
+3 |     1 + 2 = x;
+  |     ^^^^^ cannot assign to this
+  |
+  = note: only variables can be assigned to
+"
+        );
+    }
+
+    #[test]
+    fn renders_parse_errors() {
+        let text = "fn main() {\n    let x = ;\n";
+        let source = Source::new("test.inv", text);
+        let (_, errors) = Parser::new(Lexer::new(text)).parse();
+        let rendered = errors
+            .iter()
+            .map(|error| {
+                Renderer::new(false).render(&Diagnostic::from_parse_error(error, &source), &source)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            rendered,
+            [
+                "\
+error: expected one of `(`, integer literal, `true`, `false`, identifier, `!`, or `-`, found `;`
+ --> test.inv:2:13
+  |
+2 |     let x = ;
+  |             ^ expected one of `(`, integer literal, `true`, `false`, identifier, `!`, or `-`
+",
+                "\
+error: unterminated block
+ --> test.inv:2:14
+  |
+1 | fn main() {
+  |           - block opened here
+2 |     let x = ;
+  |              ^ expected `}`
+",
+            ]
+        );
+    }
+
+    #[test]
+    fn underlines_labels_on_line_breaks() {
+        let text = "let x = 1\r\nlet y = 2;";
+        let source = Source::new("test.inv", text);
+        let diagnostic = Diagnostic::error("expected `;`")
+            .with_label(Label::primary(Range::from(10..11)).with_message("add `;` here"));
+
+        assert_eq!(
+            Renderer::new(false).render(&diagnostic, &source),
+            "\
+error: expected `;`
+ --> test.inv:1:11
+  |
+1 | let x = 1
+  |          ^ add `;` here
+"
+        );
+    }
+
+    #[test]
+    fn colours_only_on_request() {
+        let source = Source::new("test.inv", "x");
+        let diagnostic = Diagnostic::error("oops").with_label(Label::primary(Range::from(0..1)));
+
+        assert!(
+            !Renderer::new(false)
+                .render(&diagnostic, &source)
+                .contains('\x1b')
+        );
+        assert!(
+            Renderer::new(true)
+                .render(&diagnostic, &source)
+                .contains(RED)
+        );
+    }
+}
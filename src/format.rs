@@ -0,0 +1,703 @@
+//! The canonical source printer behind `invariant fmt`.
+//!
+//! Blocks are indented by four spaces, opening braces stay on the line of
+//! their statement and binary operators are surrounded by single spaces.
+//! Parentheses are only written where the precedence of the operators
+//! requires them. Comments are kept: a comment that followed code on the same
+//! line stays there, a comment between the tokens of a statement or of a
+//! signature stays between the same tokens, and every other comment gets a
+//! line of its own in front of the next statement, or of the closing brace.
+
+use crate::ast::{
+    BinaryOperation, Expression, Item, Operation, Program, Statement, UnaryOperation,
+};
+use crate::lexer::Lexer;
+use crate::parser::{ParseError, Parser};
+use crate::token::Token;
+use std::range::Range;
+
+const INDENT: &str = "    ";
+
+/// Formats `source`, or returns the errors that keep it from being parsed.
+///
+/// The output uses `\r\n` line breaks if `source` does.
+pub fn format(source: &str) -> Result<String, Vec<ParseError>> {
+    let (program, errors) = Parser::new(Lexer::new(source)).parse();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // the parser drops comments, so collect them in a second pass
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.by_ref().collect::<Vec<_>>();
+
+    let formatted = Formatter::new(source, &tokens, lexer.comments()).format_program(&program);
+    if source.contains("\r\n") {
+        return Ok(formatted.replace('\n', "\r\n"));
+    }
+
+    Ok(formatted)
+}
+
+struct Formatter<'s> {
+    source: &'s str,
+    /// The tokens of the source that are yet to be written.
+    tokens: &'s [(Token, Range<usize>)],
+    comments: &'s [Range<usize>],
+    out: String,
+    indent: usize,
+    /// The end of the source last written, or `None` at the start of a
+    /// block. Blank lines in the source between it and the next line are
+    /// kept as a single blank line.
+    last: Option<usize>,
+    /// Set after an item, which is always followed by a blank line.
+    separate: bool,
+    /// Line comments found in the middle of a line. They are written at the
+    /// end of the line, or before the next token that is not a closing one,
+    /// which then goes on a continuation line.
+    pending: Vec<Range<usize>>,
+}
+
+impl<'s> Formatter<'s> {
+    /// Creates a formatter for the tree parsed from `source`, with the tokens
+    /// and the spans of the comments the lexer found in it.
+    pub fn new(
+        source: &'s str,
+        tokens: &'s [(Token, Range<usize>)],
+        comments: &'s [Range<usize>],
+    ) -> Self {
+        Self {
+            source,
+            tokens,
+            comments,
+            out: String::new(),
+            indent: 0,
+            last: None,
+            separate: false,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn format_program(mut self, program: &Program) -> String {
+        for item in program.items() {
+            self.item(item);
+        }
+        self.separate = false;
+        self.comments_before(self.source.len());
+
+        self.out
+    }
+
+    fn item(&mut self, item: &Item) {
+        match item {
+            Item::Function {
+                name,
+                arguments,
+                body,
+                span,
+                ..
+            } => {
+                self.comments_before(span.start);
+                self.start_line(span.start);
+                self.token("fn");
+                self.out.push(' ');
+                self.token(name);
+                self.token("(");
+                for (i, argument) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        self.token(",");
+                        self.out.push(' ');
+                    }
+                    self.token(argument);
+                }
+                self.token(")");
+                self.out.push(' ');
+                self.block(body);
+                self.end_line(span.end);
+            }
+        }
+
+        self.separate = true;
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        let span = statement.span();
+        self.comments_before(span.start);
+        self.start_line(span.start);
+
+        match statement {
+            Statement::Block { .. } => self.block(statement),
+            Statement::Binding { bind_to, value, .. } => {
+                self.token("let");
+                self.out.push(' ');
+                self.token(bind_to);
+                self.out.push(' ');
+                self.token("=");
+                self.out.push(' ');
+                self.expression(value);
+                self.token(";");
+            }
+            Statement::Expression { expression, .. } => {
+                self.expression(expression);
+                self.token(";");
+            }
+            Statement::Return { value, .. } => {
+                self.token("return");
+                self.out.push(' ');
+                self.expression(value);
+                self.token(";");
+            }
+            Statement::Branch {
+                condition,
+                then,
+                otherwise,
+                ..
+            } => {
+                self.token("if");
+                self.out.push(' ');
+                self.expression(condition);
+                self.out.push(' ');
+                self.block(then);
+                if let Some(otherwise) = otherwise {
+                    self.out.push(' ');
+                    self.token("else");
+                    self.out.push(' ');
+                    self.block(otherwise);
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.token("while");
+                self.out.push(' ');
+                self.expression(condition);
+                self.out.push(' ');
+                self.block(body);
+            }
+            Statement::Error { span, .. } => self.verbatim(*span),
+        }
+
+        self.end_line(span.end);
+    }
+
+    /// Writes a block, starting with its `{` on the current line and ending
+    /// with its `}`, without a line break.
+    fn block(&mut self, block: &Statement) {
+        let Statement::Block { body, span, .. } = block else {
+            // the parser only produces blocks where a block is expected
+            self.verbatim(block.span());
+            return;
+        };
+
+        self.token("{");
+        if body.is_empty() && !self.has_comment_before(span.end) {
+            self.token("}");
+            return;
+        }
+
+        self.out.push('\n');
+        self.indent += 1;
+        self.last = None;
+        for statement in body {
+            self.statement(statement);
+        }
+        self.comments_before(span.end);
+        self.indent -= 1;
+
+        self.write_indent();
+        self.token("}");
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Integer { span, .. } => self.integer(*span),
+            Expression::Boolean { value, span, .. } => self.leaf(*span, &value.to_string()),
+            Expression::Reference { name, span, .. } => self.leaf(*span, name),
+            Expression::Operation {
+                operation: Operation::Binary(operation),
+                ..
+            } => {
+                let precedence = binary_precedence(operation);
+                let (lhs, rhs) = operation.operands();
+
+                // every binary operator is left-associative
+                self.operand(lhs, precedence);
+                self.out.push(' ');
+                self.token(operation.operator().symbol());
+                self.out.push(' ');
+                self.operand(rhs, precedence + 1);
+            }
+            Expression::Operation {
+                operation: Operation::Unary(operation),
+                ..
+            } => {
+                let operand = operation.operand();
+                self.token(operation.operator().symbol());
+
+                // `-(1)` would turn into the literal `-1` without its
+                // parentheses
+                let literal = matches!(operation, UnaryOperation::Negate(_))
+                    && matches!(operand, Expression::Integer { value, .. } if *value >= 0);
+                if literal {
+                    self.parenthesized(operand);
+                } else {
+                    self.operand(operand, PREFIX_PRECEDENCE);
+                }
+            }
+            Expression::Assignment {
+                assign_to, value, ..
+            } => {
+                self.token(assign_to);
+                self.out.push(' ');
+                self.token("=");
+                self.out.push(' ');
+                // assignment is right-associative and binds the loosest, so
+                // its value never needs parentheses
+                self.expression(value);
+            }
+            Expression::Call {
+                name, arguments, ..
+            } => {
+                self.token(name);
+                self.token("(");
+                for (i, argument) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        self.token(",");
+                        self.out.push(' ');
+                    }
+                    self.expression(argument);
+                }
+                self.token(")");
+            }
+            Expression::Error { span, .. } => self.verbatim(*span),
+        }
+    }
+
+    /// Writes `operand`, in parentheses if it binds less tightly than
+    /// `precedence`.
+    fn operand(&mut self, operand: &Expression, precedence: u8) {
+        if expression_precedence(operand) < precedence {
+            self.parenthesized(operand);
+        } else {
+            self.expression(operand);
+        }
+    }
+
+    fn parenthesized(&mut self, expression: &Expression) {
+        self.token("(");
+        self.expression(expression);
+        self.token(")");
+    }
+
+    /// Writes `text`, the next token of the source, after the comments in
+    /// front of it. Parentheses the output leaves out are skipped.
+    fn token(&mut self, text: &str) {
+        let source = self.source;
+        let position = self
+            .tokens
+            .iter()
+            .take_while(|(token, span)| {
+                &source[*span] != text && matches!(token, Token::LParen | Token::RParen)
+            })
+            .count();
+
+        let closing = matches!(text, ")" | "," | ";");
+        if let Some((_, span)) = self.tokens.get(position)
+            && &source[*span] == text
+        {
+            self.comments_within(span.start, !closing);
+            self.tokens = &self.tokens[position + 1..];
+        }
+        if !closing {
+            self.write_pending(!matches!(text, "{" | "else"));
+        }
+        self.out.push_str(text);
+    }
+
+    /// Writes `text` for the source at `span`, which it may spell differently.
+    fn leaf(&mut self, span: Range<usize>, text: &str) {
+        self.comments_within(span.start, true);
+        self.skip_tokens(span.end);
+        self.write_pending(true);
+        self.out.push_str(text);
+    }
+
+    /// Writes an integer literal the way the source spells it. A negative
+    /// literal is folded from a `-` and the literal after it.
+    fn integer(&mut self, span: Range<usize>) {
+        if self.source[span].starts_with('-') {
+            self.token("-");
+        }
+        let digits = self
+            .tokens
+            .iter()
+            .find(|(token, _)| matches!(token, Token::Int(_)))
+            .map(|&(_, digits)| digits)
+            .filter(|digits| digits.start >= span.start && digits.end <= span.end)
+            .unwrap_or(span);
+
+        let source = self.source;
+        self.leaf(digits, &source[digits]);
+    }
+
+    /// Writes the source at `span` as it is.
+    fn verbatim(&mut self, span: Range<usize>) {
+        let source = self.source;
+        self.leaf(span, &source[span]);
+        // comments inside the span were written with it
+        while self.has_comment_before(span.end) {
+            self.comments = &self.comments[1..];
+        }
+    }
+
+    fn skip_tokens(&mut self, end: usize) {
+        while let Some((_, span)) = self.tokens.first()
+            && span.start < end
+        {
+            self.tokens = &self.tokens[1..];
+        }
+    }
+
+    /// Writes every block comment that starts before `offset` where the
+    /// output is, followed by a space if `space` is set. Line comments are
+    /// left for the end of the line.
+    fn comments_within(&mut self, offset: usize, space: bool) {
+        while let Some(&comment) = self.comments.first()
+            && comment.start < offset
+        {
+            self.comments = &self.comments[1..];
+            if self.source[comment].starts_with("//") {
+                self.pending.push(comment);
+                continue;
+            }
+
+            self.write_pending(true);
+            if !self.out.ends_with([' ', '\n']) {
+                self.out.push(' ');
+            }
+            self.comment(comment);
+            if space {
+                self.out.push(' ');
+            }
+        }
+    }
+
+    /// Writes the pending comments, then continues the line on the next one,
+    /// indented once more if `continuation` is set.
+    fn write_pending(&mut self, continuation: bool) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        self.write_comments();
+        self.out.truncate(self.out.trim_end_matches(' ').len());
+        self.out.push('\n');
+        self.write_indent();
+        if continuation {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    /// Writes the pending line comments at the end of the current line, all
+    /// but the first on a line of their own.
+    fn write_comments(&mut self) {
+        for (i, comment) in std::mem::take(&mut self.pending).into_iter().enumerate() {
+            if i > 0 {
+                self.out.push('\n');
+                self.write_indent();
+            } else if !self.out.ends_with([' ', '\n']) {
+                self.out.push(' ');
+            }
+            self.comment(comment);
+        }
+    }
+
+    /// Writes every comment that starts before `offset` on a line of its own.
+    fn comments_before(&mut self, offset: usize) {
+        while let Some(&comment) = self.comments.first()
+            && comment.start < offset
+        {
+            self.comments = &self.comments[1..];
+            self.start_line(comment.start);
+            self.comment(comment);
+            self.end_line(comment.end);
+        }
+    }
+
+    fn has_comment_before(&self, offset: usize) -> bool {
+        self.comments
+            .first()
+            .is_some_and(|comment| comment.start < offset)
+    }
+
+    /// Indents a new line for source starting at `start`, preceded by a blank
+    /// line if there was one in the source or the last item asks for one.
+    fn start_line(&mut self, start: usize) {
+        if let Some(last) = self.last
+            && (self.separate
+                || last <= start && self.source[last..start].matches('\n').count() > 1)
+        {
+            self.out.push('\n');
+        }
+        self.separate = false;
+
+        self.write_indent();
+    }
+
+    /// Ends the line of source ending at `end` after the pending comments,
+    /// keeping a comment that follows on the same line, unless another token
+    /// comes before it.
+    fn end_line(&mut self, end: usize) {
+        let mut end = end;
+        if let Some(&comment) = self.comments.first()
+            && comment.start >= end
+            && !self.source[end..comment.start].contains('\n')
+            && self
+                .tokens
+                .first()
+                .is_none_or(|(_, span)| span.start > comment.start)
+        {
+            self.comments = &self.comments[1..];
+            self.pending.push(comment);
+        }
+        end = self
+            .pending
+            .last()
+            .map_or(end, |comment| end.max(comment.end));
+        self.write_comments();
+
+        self.out.push('\n');
+        self.last = Some(end);
+    }
+
+    fn comment(&mut self, comment: Range<usize>) {
+        let text = self.source[comment].trim_end();
+        self.out.push_str(&text.replace("\r\n", "\n"));
+    }
+
+    fn write_indent(&mut self) {
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+}
+
+/// Above every binary operator, see `Parser::binding_power`.
+const PREFIX_PRECEDENCE: u8 = 8;
+
+fn expression_precedence(expression: &Expression) -> u8 {
+    match expression {
+        Expression::Assignment { .. } => 1,
+        Expression::Operation {
+            operation: Operation::Binary(operation),
+            ..
+        } => binary_precedence(operation),
+        Expression::Operation {
+            operation: Operation::Unary(_),
+            ..
+        } => PREFIX_PRECEDENCE,
+        _ => u8::MAX,
+    }
+}
+
+/// Matches `Parser::binding_power` of the operator.
+fn binary_precedence(operation: &BinaryOperation) -> u8 {
+    match operation {
+        BinaryOperation::Or { .. } => 2,
+        BinaryOperation::And { .. } => 3,
+        BinaryOperation::Equal { .. } | BinaryOperation::NotEqual { .. } => 4,
+        BinaryOperation::Less { .. }
+        | BinaryOperation::LessEqual { .. }
+        | BinaryOperation::Greater { .. }
+        | BinaryOperation::GreaterEqual { .. } => 5,
+        BinaryOperation::Add { .. } | BinaryOperation::Sub { .. } => 6,
+        BinaryOperation::Mul { .. } | BinaryOperation::Div { .. } => 7,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatted(source: &str) -> String {
+        format(source).unwrap_or_else(|errors| panic!("`{source}` failed to parse: {errors:?}"))
+    }
+
+    fn formatted_expression(expression: &str) -> String {
+        let formatted = formatted(&format!("fn f() {{ {expression}; }}"));
+
+        formatted
+            .strip_prefix("fn f() {\n    ")
+            .and_then(|formatted| formatted.strip_suffix(";\n}\n"))
+            .expect("a single expression statement")
+            .to_string()
+    }
+
+    #[test]
+    fn lays_out_statements() {
+        assert_eq!(
+            formatted(
+                "fn  f(a,b){let x=a;while x<b{x=x+1;}if x==b{return x;}else{}} fn g(){f(1,2);}"
+            ),
+            "\
+fn f(a, b) {
+    let x = a;
+    while x < b {
+        x = x + 1;
+    }
+    if x == b {
+        return x;
+    } else {}
+}
+
+fn g() {
+    f(1, 2);
+}
+"
+        );
+    }
+
+    #[test]
+    fn writes_only_necessary_parentheses() {
+        let cases = [
+            ("((a + b)) * c", "(a + b) * c"),
+            ("a + (b * c)", "a + b * c"),
+            ("(a - b) - c", "a - b - c"),
+            ("a - (b - c)", "a - (b - c)"),
+            ("a / (b * c)", "a / (b * c)"),
+            ("(a || b) && !(c || d)", "(a || b) && !(c || d)"),
+            ("a || (b && c)", "a || b && c"),
+            ("(a < b) == (c < d)", "a < b == c < d"),
+            ("(a == b) == c", "a == b == c"),
+            ("x = (y = (1 + 2))", "x = y = 1 + 2"),
+            ("(x = 1) + 2", "(x = 1) + 2"),
+            ("-(-x)", "--x"),
+            ("- -1", "--1"),
+            ("-(1)", "-(1)"),
+            ("-(1 + x)", "-(1 + x)"),
+            ("(-1) * x", "-1 * x"),
+            ("f((1), (g(a + b)))", "f(1, g(a + b))"),
+        ];
+
+        for (source, expected) in cases {
+            assert_eq!(formatted_expression(source), expected, "for `{source}`");
+        }
+    }
+
+    #[test]
+    fn keeps_the_spelling_of_literals() {
+        for source in ["0xFF + 0b1111_0000 * 0o17 - 1_000_000", "-0x10", "f(-1_0)"] {
+            assert_eq!(formatted_expression(source), source);
+        }
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let source = "\
+// leading
+fn f() { // after the brace
+  let x = 1;   // trailing
+
+
+  /* block */ return x;
+  // at the end
+}
+// at the end of the file
+";
+
+        assert_eq!(
+            formatted(source),
+            "\
+// leading
+fn f() {
+    // after the brace
+    let x = 1; // trailing
+
+    /* block */
+    return x;
+    // at the end
+}
+// at the end of the file
+"
+        );
+    }
+
+    #[test]
+    fn keeps_comments_in_empty_blocks() {
+        assert_eq!(
+            formatted("fn f() { if true { /* nothing */ } }"),
+            "\
+fn f() {
+    if true {
+        /* nothing */
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn keeps_comments_between_tokens() {
+        let cases = [
+            (
+                "fn f() { let x = 1 + /* c */ 2; }",
+                "fn f() {\n    let x = 1 + /* c */ 2;\n}\n",
+            ),
+            (
+                "fn f() {\n    let x = 1 // c\n;\n}",
+                "fn f() {\n    let x = 1; // c\n}\n",
+            ),
+            (
+                "fn f() { g(a /* c */); x /* d */ = (1 /* e */); }",
+                "fn f() {\n    g(a /* c */);\n    x /* d */ = 1 /* e */;\n}\n",
+            ),
+            (
+                "fn f() {\n    if x {\n        a();\n    } // c\n    else {}\n}",
+                "fn f() {\n    if x {\n        a();\n    } // c\n    else {}\n}\n",
+            ),
+            (
+                "fn f(a, /* c */ b) { return a; }",
+                "fn f(a, /* c */ b) {\n    return a;\n}\n",
+            ),
+            ("fn f(a // c\n, b) {}", "fn f(a, // c\n    b) {}\n"),
+            (
+                "fn f() {\n    g(a, // c\n    /* d */ b);\n}",
+                "fn f() {\n    g(a, // c\n        /* d */ b);\n}\n",
+            ),
+        ];
+
+        for (source, expected) in cases {
+            assert_eq!(formatted(source), expected, "for `{source}`");
+            assert_eq!(formatted(expected), expected, "for `{expected}`");
+        }
+    }
+
+    #[test]
+    fn separates_items_by_one_blank_line() {
+        assert_eq!(
+            formatted("fn a() {}\n\n\n\n// b\nfn b() {}\nfn c() {}"),
+            "fn a() {}\n\n// b\nfn b() {}\n\nfn c() {}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_crlf_line_breaks() {
+        assert_eq!(
+            formatted("fn f() {\r\n  return 1; // one\r\n}\r\n"),
+            "fn f() {\r\n    return 1; // one\r\n}\r\n"
+        );
+    }
+
+    #[test]
+    fn is_idempotent() {
+        let source = include_str!("../examples/example2.inv");
+        let once = formatted(source);
+
+        assert_eq!(formatted(&once), once);
+    }
+
+    #[test]
+    fn refuses_to_format_invalid_source() {
+        assert!(format("fn f() { let = 1; }").is_err());
+    }
+}
@@ -0,0 +1,627 @@
+//! A tree-walking interpreter.
+//!
+//! Integers are 64 bits wide and overflow is an error, as is division by
+//! zero. Operators only accept operands of the types they are defined for;
+//! there are no implicit conversions. A function that ends without `return`
+//! returns `()`.
+
+use crate::ast::{BinaryOperator, Expression, Item, Operation, Program, Statement, UnaryOperator};
+use crate::check::Builtin;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::range::Range;
+use std::sync::Arc;
+
+/// How deeply calls may nest before the program is stopped.
+pub const MAX_CALL_DEPTH: usize = 2_000;
+
+/// The stack of the thread that runs a program. Unoptimized builds need
+/// about 16 KiB per nested call.
+const STACK_SIZE: usize = 64 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    Integer(i64),
+    Boolean(bool),
+    /// The result of calls to functions that do not return anything.
+    Unit,
+}
+
+impl Value {
+    pub fn type_name(self) -> &'static str {
+        match self {
+            Value::Integer(_) => "int",
+            Value::Boolean(_) => "bool",
+            Value::Unit => "()",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Integer(value) => write!(f, "{value}"),
+            Value::Boolean(value) => write!(f, "{value}"),
+            Value::Unit => f.write_str("()"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeError {
+    DivisionByZero(Range<usize>),
+    Overflow(Range<usize>),
+    /// A value of the wrong type, together with the type that was expected.
+    TypeMismatch {
+        expected: &'static str,
+        found: Value,
+        span: Range<usize>,
+    },
+    UndefinedVariable {
+        name: String,
+        span: Range<usize>,
+    },
+    UndefinedFunction {
+        name: String,
+        span: Range<usize>,
+    },
+    ArityMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+        span: Range<usize>,
+    },
+    /// Calls nested deeper than `MAX_CALL_DEPTH`, at the call that went too
+    /// deep.
+    StackOverflow(Range<usize>),
+    /// The program has no `main` function to start from.
+    MissingMain,
+    /// The expression or statement could not be run because it did not
+    /// parse.
+    Error(Range<usize>),
+    /// Writing the output of `print` failed.
+    Output(String),
+}
+
+impl RuntimeError {
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            RuntimeError::DivisionByZero(span)
+            | RuntimeError::Overflow(span)
+            | RuntimeError::TypeMismatch { span, .. }
+            | RuntimeError::UndefinedVariable { span, .. }
+            | RuntimeError::UndefinedFunction { span, .. }
+            | RuntimeError::ArityMismatch { span, .. }
+            | RuntimeError::StackOverflow(span)
+            | RuntimeError::Error(span) => Some(*span),
+            RuntimeError::MissingMain | RuntimeError::Output(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::DivisionByZero(_) => write!(f, "division by zero"),
+            RuntimeError::Overflow(_) => write!(f, "integer overflow"),
+            RuntimeError::TypeMismatch {
+                expected, found, ..
+            } => write!(
+                f,
+                "expected a value of type `{expected}`, found `{found}` of type `{}`",
+                found.type_name()
+            ),
+            RuntimeError::UndefinedVariable { name, .. } => {
+                write!(f, "cannot find variable `{name}`")
+            }
+            RuntimeError::UndefinedFunction { name, .. } => {
+                write!(f, "cannot find function `{name}`")
+            }
+            RuntimeError::ArityMismatch {
+                name,
+                expected,
+                found,
+                ..
+            } => write!(
+                f,
+                "`{name}` takes {expected} arguments but was called with {found}"
+            ),
+            RuntimeError::StackOverflow(_) => {
+                write!(f, "calls nested more than {MAX_CALL_DEPTH} deep")
+            }
+            RuntimeError::MissingMain => write!(f, "the program has no `main` function"),
+            RuntimeError::Error(_) => write!(f, "cannot run code that failed to parse"),
+            RuntimeError::Output(error) => write!(f, "could not write output: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+/// What running a statement did to the control flow.
+enum Flow {
+    Next,
+    Return(Value),
+}
+
+/// Runs programs, writing the output of `print` to `out`.
+///
+/// The interpreter keeps the functions of every program it loaded, so a
+/// program may call functions from one loaded before it.
+pub struct Interpreter<W> {
+    functions: HashMap<String, Arc<Item>>,
+    /// The variables of the running call, innermost block last.
+    scopes: Vec<HashMap<String, Value>>,
+    depth: usize,
+    out: W,
+}
+
+impl<W> Interpreter<W> {
+    pub fn function(&self, name: &str) -> Option<&Item> {
+        self.functions.get(name).map(Arc::as_ref)
+    }
+
+    /// The value of the variable `name`. Outside of a call, these are the
+    /// variables bound by `evaluate_statement`.
+    pub fn lookup(&self, name: &str) -> Option<Value> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+}
+
+impl<W: Write + Send> Interpreter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            functions: HashMap::new(),
+            scopes: vec![HashMap::new()],
+            depth: 0,
+            out,
+        }
+    }
+
+    /// Adds the functions of `program`, replacing loaded functions of the
+    /// same name.
+    pub fn load(&mut self, program: Program) {
+        for item in program.into_items() {
+            self.define(item);
+        }
+    }
+
+    /// Adds a function, replacing a loaded function of the same name.
+    pub fn define(&mut self, item: Item) {
+        self.functions
+            .insert(item.name().to_string(), Arc::new(item));
+    }
+
+    /// Calls the `main` function. The program runs on a thread of its own,
+    /// whose stack is large enough for `MAX_CALL_DEPTH` nested calls.
+    pub fn run(&mut self) -> Result<Value, RuntimeError> {
+        if !self.functions.contains_key("main") {
+            return Err(RuntimeError::MissingMain);
+        }
+
+        self.on_large_stack(|interpreter| interpreter.call("main", Vec::new(), Range::from(0..0)))
+    }
+
+    /// Runs a statement outside of any function. Its `let`s bind variables
+    /// that later statements can use. Returns the value of an expression
+    /// statement or `return`, and `()` for every other statement.
+    pub fn evaluate_statement(&mut self, statement: &Statement) -> Result<Value, RuntimeError> {
+        self.on_large_stack(|interpreter| match statement {
+            Statement::Expression { expression, .. } => interpreter.evaluate(expression),
+            _ => match interpreter.execute(statement)? {
+                Flow::Next => Ok(Value::Unit),
+                Flow::Return(value) => Ok(value),
+            },
+        })
+    }
+
+    fn on_large_stack<T: Send>(&mut self, f: impl FnOnce(&mut Self) -> T + Send) -> T {
+        std::thread::scope(|scope| {
+            let thread = std::thread::Builder::new()
+                .name("interpreter".to_string())
+                .stack_size(STACK_SIZE)
+                .spawn_scoped(scope, || f(self))
+                .expect("the interpreter thread should start");
+
+            thread
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        })
+    }
+
+    pub fn output(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    pub fn into_output(self) -> W {
+        self.out
+    }
+
+    fn call(
+        &mut self,
+        name: &str,
+        arguments: Vec<Value>,
+        span: Range<usize>,
+    ) -> Result<Value, RuntimeError> {
+        let Some(function) = self.functions.get(name).map(Arc::clone) else {
+            return match Builtin::lookup(name) {
+                Some(builtin) => self.call_builtin(builtin, &arguments, span),
+                None => Err(RuntimeError::UndefinedFunction {
+                    name: name.to_string(),
+                    span,
+                }),
+            };
+        };
+        let Item::Function {
+            arguments: parameters,
+            body,
+            ..
+        } = function.as_ref();
+
+        if parameters.len() != arguments.len() {
+            return Err(RuntimeError::ArityMismatch {
+                name: name.to_string(),
+                expected: parameters.len(),
+                found: arguments.len(),
+                span,
+            });
+        }
+        if self.depth == MAX_CALL_DEPTH {
+            return Err(RuntimeError::StackOverflow(span));
+        }
+
+        let frame = vec![parameters.iter().cloned().zip(arguments).collect()];
+        let caller = std::mem::replace(&mut self.scopes, frame);
+        self.depth += 1;
+
+        let result = self.execute(body);
+
+        self.depth -= 1;
+        self.scopes = caller;
+
+        match result? {
+            Flow::Next => Ok(Value::Unit),
+            Flow::Return(value) => Ok(value),
+        }
+    }
+
+    fn call_builtin(
+        &mut self,
+        builtin: Builtin,
+        arguments: &[Value],
+        span: Range<usize>,
+    ) -> Result<Value, RuntimeError> {
+        if arguments.len() != builtin.arity() {
+            return Err(RuntimeError::ArityMismatch {
+                name: builtin.name().to_string(),
+                expected: builtin.arity(),
+                found: arguments.len(),
+                span,
+            });
+        }
+
+        match builtin {
+            Builtin::Print => {
+                writeln!(self.out, "{}", arguments[0])
+                    .map_err(|error| RuntimeError::Output(error.to_string()))?;
+
+                Ok(Value::Unit)
+            }
+        }
+    }
+
+    fn execute(&mut self, statement: &Statement) -> Result<Flow, RuntimeError> {
+        match statement {
+            Statement::Block { body, .. } => {
+                self.scopes.push(HashMap::new());
+                let flow = self.execute_block(body);
+                self.scopes.pop();
+
+                flow
+            }
+            Statement::Binding { bind_to, value, .. } => {
+                let value = self.evaluate(value)?;
+                self.scopes
+                    .last_mut()
+                    .expect("there is always a scope")
+                    .insert(bind_to.clone(), value);
+
+                Ok(Flow::Next)
+            }
+            Statement::Expression { expression, .. } => {
+                self.evaluate(expression)?;
+
+                Ok(Flow::Next)
+            }
+            Statement::Return { value, .. } => Ok(Flow::Return(self.evaluate(value)?)),
+            Statement::Branch {
+                condition,
+                then,
+                otherwise,
+                ..
+            } => {
+                if self.condition(condition)? {
+                    self.execute(then)
+                } else if let Some(otherwise) = otherwise {
+                    self.execute(otherwise)
+                } else {
+                    Ok(Flow::Next)
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                while self.condition(condition)? {
+                    if let Flow::Return(value) = self.execute(body)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+
+                Ok(Flow::Next)
+            }
+            Statement::Error { span, .. } => Err(RuntimeError::Error(*span)),
+        }
+    }
+
+    fn execute_block(&mut self, body: &[Statement]) -> Result<Flow, RuntimeError> {
+        for statement in body {
+            if let Flow::Return(value) = self.execute(statement)? {
+                return Ok(Flow::Return(value));
+            }
+        }
+
+        Ok(Flow::Next)
+    }
+
+    fn condition(&mut self, condition: &Expression) -> Result<bool, RuntimeError> {
+        let value = self.evaluate(condition)?;
+
+        boolean(value, condition.span())
+    }
+
+    fn variable(&mut self, name: &str, span: Range<usize>) -> Result<&mut Value, RuntimeError> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+            .ok_or_else(|| RuntimeError::UndefinedVariable {
+                name: name.to_string(),
+                span,
+            })
+    }
+
+    fn evaluate(&mut self, expression: &Expression) -> Result<Value, RuntimeError> {
+        match expression {
+            Expression::Integer { value, .. } => Ok(Value::Integer(*value)),
+            Expression::Boolean { value, .. } => Ok(Value::Boolean(*value)),
+            Expression::Reference { name, span, .. } => self.variable(name, *span).copied(),
+            Expression::Assignment {
+                assign_to,
+                value,
+                span,
+                ..
+            } => {
+                let value = self.evaluate(value)?;
+                *self.variable(assign_to, *span)? = value;
+
+                Ok(value)
+            }
+            Expression::Operation {
+                operation: Operation::Unary(operation),
+                span,
+                ..
+            } => {
+                let operand = operation.operand();
+                let value = self.evaluate(operand)?;
+
+                match operation.operator() {
+                    UnaryOperator::Not => Ok(Value::Boolean(!boolean(value, operand.span())?)),
+                    UnaryOperator::Negate => integer(value, operand.span())?
+                        .checked_neg()
+                        .map(Value::Integer)
+                        .ok_or(RuntimeError::Overflow(*span)),
+                }
+            }
+            Expression::Operation {
+                operation: Operation::Binary(operation),
+                span,
+                ..
+            } => {
+                let (lhs, rhs) = operation.operands();
+                self.binary(operation.operator(), lhs, rhs, *span)
+            }
+            Expression::Call {
+                name,
+                arguments,
+                span,
+                ..
+            } => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.evaluate(argument))
+                    .collect::<Result<_, _>>()?;
+
+                self.call(name, arguments, *span)
+            }
+            Expression::Error { span, .. } => Err(RuntimeError::Error(*span)),
+        }
+    }
+
+    fn binary(
+        &mut self,
+        operator: BinaryOperator,
+        lhs: &Expression,
+        rhs: &Expression,
+        span: Range<usize>,
+    ) -> Result<Value, RuntimeError> {
+        let left = self.evaluate(lhs)?;
+
+        // `&&` and `||` only evaluate their right operand if it matters
+        match operator {
+            BinaryOperator::And if !boolean(left, lhs.span())? => return Ok(Value::Boolean(false)),
+            BinaryOperator::Or if boolean(left, lhs.span())? => return Ok(Value::Boolean(true)),
+            BinaryOperator::And | BinaryOperator::Or => {
+                let right = self.evaluate(rhs)?;
+                return Ok(Value::Boolean(boolean(right, rhs.span())?));
+            }
+            _ => {}
+        }
+
+        let right = self.evaluate(rhs)?;
+        if let BinaryOperator::Equal | BinaryOperator::NotEqual = operator {
+            if left.type_name() != right.type_name() {
+                return Err(RuntimeError::TypeMismatch {
+                    expected: left.type_name(),
+                    found: right,
+                    span: rhs.span(),
+                });
+            }
+
+            return Ok(Value::Boolean(
+                (left == right) == (operator == BinaryOperator::Equal),
+            ));
+        }
+
+        let (left, right) = (integer(left, lhs.span())?, integer(right, rhs.span())?);
+        let arithmetic = |result: Option<i64>| {
+            result
+                .map(Value::Integer)
+                .ok_or(RuntimeError::Overflow(span))
+        };
+
+        match operator {
+            BinaryOperator::Add => arithmetic(left.checked_add(right)),
+            BinaryOperator::Sub => arithmetic(left.checked_sub(right)),
+            BinaryOperator::Mul => arithmetic(left.checked_mul(right)),
+            BinaryOperator::Div if right == 0 => Err(RuntimeError::DivisionByZero(span)),
+            BinaryOperator::Div => arithmetic(left.checked_div(right)),
+            BinaryOperator::Less => Ok(Value::Boolean(left < right)),
+            BinaryOperator::LessEqual => Ok(Value::Boolean(left <= right)),
+            BinaryOperator::Greater => Ok(Value::Boolean(left > right)),
+            BinaryOperator::GreaterEqual => Ok(Value::Boolean(left >= right)),
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::And
+            | BinaryOperator::Or => unreachable!("handled above"),
+        }
+    }
+}
+
+fn integer(value: Value, span: Range<usize>) -> Result<i64, RuntimeError> {
+    match value {
+        Value::Integer(value) => Ok(value),
+        found => Err(RuntimeError::TypeMismatch {
+            expected: "int",
+            found,
+            span,
+        }),
+    }
+}
+
+fn boolean(value: Value, span: Range<usize>) -> Result<bool, RuntimeError> {
+    match value {
+        Value::Boolean(value) => Ok(value),
+        found => Err(RuntimeError::TypeMismatch {
+            expected: "bool",
+            found,
+            span,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn run(source: &str) -> (Result<Value, RuntimeError>, String) {
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+        assert!(errors.is_empty(), "`{source}` failed to parse: {errors:?}");
+
+        let mut interpreter = Interpreter::new(Vec::new());
+        interpreter.load(program);
+        let result = interpreter.run();
+        let output = String::from_utf8(interpreter.into_output()).unwrap();
+
+        (result, output)
+    }
+
+    fn error(source: &str) -> String {
+        let (result, _) = run(source);
+        let error = result.unwrap_err();
+
+        match error.span() {
+            Some(span) => format!("{}: {error}", &source[span]),
+            None => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn runs_the_examples() {
+        let source = include_str!("../examples/example2.inv");
+
+        assert_eq!(run(source), (Ok(Value::Integer(0)), "37\n".to_string()));
+    }
+
+    #[test]
+    fn evaluates_operators() {
+        let (result, output) = run("fn main() { \
+             print(7 / 2 * 2 - -3); print(1 < 2 && 2 <= 2 && !(3 > 4) && 4 >= 4); \
+             print(true == false || 1 != 2); print(f()); }\
+             fn f() { if false { return 1; } }");
+
+        assert_eq!(result, Ok(Value::Unit));
+        assert_eq!(output, "9\ntrue\ntrue\n()\n");
+    }
+
+    #[test]
+    fn scopes_variables_to_blocks_and_calls() {
+        let (result, _) = run(
+            "fn main() { let x = 1; { let x = 2; x = 3; } return f(x) + x; } \
+             fn f(x) { x = x * 10; return x; }",
+        );
+
+        assert_eq!(result, Ok(Value::Integer(11)));
+    }
+
+    #[test]
+    fn short_circuits_logical_operators() {
+        let (result, output) = run("fn main() { return false && loud() || true || loud(); } \
+             fn loud() { print(1); return true; }");
+
+        assert_eq!(result, Ok(Value::Boolean(true)));
+        assert_eq!(output, "");
+    }
+
+    #[test]
+    fn reports_runtime_errors() {
+        assert_eq!(
+            error("fn main() { let x = 0; return 1 / x; }"),
+            "1 / x: division by zero"
+        );
+        assert_eq!(
+            error("fn main() { return 9223372036854775807 + 1; }"),
+            "9223372036854775807 + 1: integer overflow"
+        );
+        assert_eq!(
+            error("fn main() { if 1 {} }"),
+            "1: expected a value of type `bool`, found `1` of type `int`"
+        );
+        assert_eq!(
+            error("fn main() { return 1 == true; }"),
+            "true: expected a value of type `int`, found `true` of type `bool`"
+        );
+        assert_eq!(
+            error(
+                "fn main() { return f(0); } fn f(n) { if n < 0 { return n; } return f(n + 1) * 2; }"
+            ),
+            "f(n + 1): calls nested more than 2000 deep"
+        );
+        assert_eq!(error("fn f() {}"), "the program has no `main` function");
+    }
+}
@@ -3,9 +3,10 @@ use std::iter::Peekable;
 use std::range::Range;
 use std::str::Chars;
 
-pub(crate) struct Lexer<'c> {
+pub struct Lexer<'c> {
     input: Peekable<Chars<'c>>,
     position: usize,
+    comments: Vec<Range<usize>>,
 }
 
 impl<'c> Lexer<'c> {
@@ -13,9 +14,16 @@ impl<'c> Lexer<'c> {
         Self {
             input: input.chars().peekable(),
             position: 0,
+            comments: Vec::new(),
         }
     }
 
+    /// The spans of the comments skipped so far. Line comments do not include
+    /// the line break that ends them.
+    pub fn comments(&self) -> &[Range<usize>] {
+        &self.comments
+    }
+
     fn next_token(&mut self) -> Option<(Token, Range<usize>)> {
         let current_position = self.position;
         match self.next_char()? {
@@ -28,44 +36,62 @@ impl<'c> Lexer<'c> {
             '+' => self.emit_token(current_position, Token::Plus),
             '-' => self.emit_token(current_position, Token::Minus),
             '*' => self.emit_token(current_position, Token::Asterisk),
-            '/' => self.emit_token(current_position, Token::Slash),
+            '/' => {
+                if self.next_char_if(|c| c == '/').is_some() {
+                    while self.next_char_if(|c| c != '\n').is_some() {}
+                    self.comments
+                        .push(Range::from(current_position..self.position));
+                    return self.next_token();
+                }
+
+                if self.next_char_if(|c| c == '*').is_some() {
+                    if !self.skip_block_comment() {
+                        return self.emit_token(current_position, Token::UnterminatedComment);
+                    }
+                    self.comments
+                        .push(Range::from(current_position..self.position));
+                    return self.next_token();
+                }
+
+                self.emit_token(current_position, Token::Slash)
+            }
             '!' => {
-                if let Some(_) = self.next_char_if(|c| c == '=') {
+                if self.next_char_if(|c| c == '=').is_some() {
                     return self.emit_token(current_position, Token::BangEqual);
                 }
 
                 self.emit_token(current_position, Token::Bang)
             }
             '=' => {
-                if let Some(_) = self.next_char_if(|c| c == '=') {
+                if self.next_char_if(|c| c == '=').is_some() {
                     return self.emit_token(current_position, Token::EqualEqual);
                 }
 
                 self.emit_token(current_position, Token::Equal)
             }
             '<' => {
-                if let Some(_) = self.next_char_if(|c| c == '=') {
+                if self.next_char_if(|c| c == '=').is_some() {
                     return self.emit_token(current_position, Token::LessEqual);
                 }
 
                 self.emit_token(current_position, Token::Less)
             }
             '>' => {
-                if let Some(_) = self.next_char_if(|c| c == '=') {
+                if self.next_char_if(|c| c == '=').is_some() {
                     return self.emit_token(current_position, Token::GreaterEqual);
                 }
 
                 self.emit_token(current_position, Token::Greater)
             }
             '&' => {
-                if let Some(_) = self.next_char_if(|c| c == '&') {
+                if self.next_char_if(|c| c == '&').is_some() {
                     return self.emit_token(current_position, Token::AndAnd);
                 }
 
                 self.emit_token(current_position, Token::UnexpectedCharacter('&'))
             }
             '|' => {
-                if let Some(_) = self.next_char_if(|c| c == '|') {
+                if self.next_char_if(|c| c == '|').is_some() {
                     return self.emit_token(current_position, Token::OrOr);
                 }
 
@@ -89,27 +115,45 @@ impl<'c> Lexer<'c> {
                     _ => self.emit_token(current_position, Token::Identifier(identifier)),
                 }
             }
-            c if c.is_numeric() => {
-                let mut number = String::from(c);
-                while let Some(c) = self.next_char_if(|c| c.is_numeric()) {
-                    number.push(c);
+            c if c.is_ascii_digit() => {
+                // take every character that could belong to the literal, so
+                // that `0xZZ` or `12ab` are reported as one malformed literal
+                let mut literal = String::from(c);
+                while let Some(c) = self.next_char_if(|c| c.is_alphanumeric() || c == '_') {
+                    literal.push(c);
                 }
 
-                self.emit_token(
-                    current_position,
-                    Token::Int(
-                        number
-                            .parse()
-                            .expect("This cannot fail due to how we constructed `number`"),
-                    ),
-                )
+                let token = match parse_integer(&literal) {
+                    Ok(value) => Token::Int(value),
+                    Err(IntegerError::Overflow) => Token::IntOverflow(literal),
+                    Err(IntegerError::Malformed) => Token::InvalidInt(literal),
+                };
+                self.emit_token(current_position, token)
             }
             c if c.is_whitespace() => {
                 while self.next_char_if(|c| c.is_whitespace()).is_some() {}
                 self.next_token()
             }
-            _ => todo!(),
+            c => self.emit_token(current_position, Token::UnexpectedCharacter(c)),
+        }
+    }
+
+    /// Skips the rest of a block comment whose `/*` has already been
+    /// consumed. Block comments nest, so every `/*` needs its own `*/`.
+    /// Returns `false` if the input ends before the comment is closed.
+    fn skip_block_comment(&mut self) -> bool {
+        let mut depth = 1;
+
+        while depth > 0 {
+            match self.next_char() {
+                Some('/') if self.next_char_if(|c| c == '*').is_some() => depth += 1,
+                Some('*') if self.next_char_if(|c| c == '/').is_some() => depth -= 1,
+                Some(_) => {}
+                None => return false,
+            }
         }
+
+        true
     }
 
     fn next_char(&mut self) -> Option<char> {
@@ -127,6 +171,40 @@ impl<'c> Lexer<'c> {
     }
 }
 
+enum IntegerError {
+    Malformed,
+    Overflow,
+}
+
+/// Parses a decimal literal or one prefixed with `0x`, `0o` or `0b`. Digits
+/// may be separated by single underscores.
+fn parse_integer(literal: &str) -> Result<u64, IntegerError> {
+    let (radix, digits) = match literal.get(..2) {
+        Some("0x") => (16, &literal[2..]),
+        Some("0o") => (8, &literal[2..]),
+        Some("0b") => (2, &literal[2..]),
+        _ => (10, literal),
+    };
+
+    if digits.is_empty()
+        || digits.starts_with('_')
+        || digits.ends_with('_')
+        || digits.contains("__")
+    {
+        return Err(IntegerError::Malformed);
+    }
+
+    let mut value = Some(0u64);
+    for c in digits.chars().filter(|&c| c != '_') {
+        let digit = c.to_digit(radix).ok_or(IntegerError::Malformed)?;
+        value = value
+            .and_then(|value| value.checked_mul(u64::from(radix)))
+            .and_then(|value| value.checked_add(u64::from(digit)));
+    }
+
+    value.ok_or(IntegerError::Overflow)
+}
+
 impl<'c> Iterator for Lexer<'c> {
     type Item = (Token, Range<usize>);
 
@@ -134,3 +212,153 @@ impl<'c> Iterator for Lexer<'c> {
         self.next_token()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emits_error_tokens_for_unknown_characters() {
+        let source = "a % _ @ \"ä\" & | && ||";
+        let tokens = Lexer::new(source)
+            .map(|(token, span)| (token.to_string(), &source[span]))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            [
+                ("identifier `a`".to_string(), "a"),
+                ("character `%`".to_string(), "%"),
+                ("character `_`".to_string(), "_"),
+                ("character `@`".to_string(), "@"),
+                ("character `\"`".to_string(), "\""),
+                ("identifier `ä`".to_string(), "ä"),
+                ("character `\"`".to_string(), "\""),
+                ("character `&`".to_string(), "&"),
+                ("character `|`".to_string(), "|"),
+                ("`&&`".to_string(), "&&"),
+                ("`||`".to_string(), "||"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_overflowing_integers_as_error_tokens() {
+        let source = "18446744073709551615 0x1_0000_0000_0000_0000";
+        let tokens = Lexer::new(source).collect::<Vec<_>>();
+
+        assert!(matches!(
+            tokens.as_slice(),
+            [
+                (Token::Int(u64::MAX), _),
+                (Token::IntOverflow(literal), span),
+            ] if literal == "0x1_0000_0000_0000_0000" && *span == Range::from(21..44)
+        ));
+    }
+
+    #[test]
+    fn skips_comments() {
+        let source = "a // line comment\nb /* block /* nested */ still comment */ c / d /**/ e //";
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer
+            .by_ref()
+            .map(|(_, span)| &source[span])
+            .collect::<Vec<_>>();
+        let comments = lexer
+            .comments()
+            .iter()
+            .map(|&span| &source[span])
+            .collect::<Vec<_>>();
+
+        assert_eq!(tokens, ["a", "b", "c", "/", "d", "e"]);
+        assert_eq!(
+            comments,
+            [
+                "// line comment",
+                "/* block /* nested */ still comment */",
+                "/**/",
+                "//"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unterminated_block_comments() {
+        let cases = [
+            ("a /* never closed", 2..17),
+            ("a /* /* */", 2..10),
+            ("a /*/", 2..5),
+        ];
+
+        for (source, span) in cases {
+            let tokens = Lexer::new(source).collect::<Vec<_>>();
+            assert!(
+                matches!(
+                    tokens.as_slice(),
+                    [(Token::Identifier(_), _), (Token::UnterminatedComment, reported)]
+                        if *reported == Range::from(span.clone())
+                ),
+                "while lexing `{source}`"
+            );
+        }
+    }
+
+    #[test]
+    fn lexes_integer_literals() {
+        let cases = [
+            ("0", 0),
+            ("1_000_000", 1_000_000),
+            ("0x2A", 42),
+            ("0xdead_beef", 0xdead_beef),
+            ("0o777", 0o777),
+            ("0b1010_1010", 0b1010_1010),
+            ("007", 7),
+            ("0xFFFF_FFFF_FFFF_FFFF", u64::MAX),
+        ];
+
+        for (source, expected) in cases {
+            let tokens = Lexer::new(source).collect::<Vec<_>>();
+            assert!(
+                matches!(tokens.as_slice(), [(Token::Int(value), _)] if *value == expected),
+                "while lexing `{source}`"
+            );
+        }
+    }
+
+    #[test]
+    fn reports_malformed_integer_literals() {
+        let cases = [
+            "0xZZ", "1__0", "1_", "0x", "0x_1", "0b102", "0o8", "12ab", "0X1", "1ä",
+        ];
+
+        for source in cases {
+            let tokens = Lexer::new(source).collect::<Vec<_>>();
+            assert!(
+                matches!(
+                    tokens.as_slice(),
+                    [(Token::InvalidInt(literal), span)]
+                        if literal == source && *span == Range::from(0..source.len())
+                ),
+                "while lexing `{source}`"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_non_ascii_digits() {
+        let tokens = Lexer::new("٣").collect::<Vec<_>>();
+
+        assert!(matches!(
+            tokens.as_slice(),
+            [(Token::UnexpectedCharacter('٣'), _)]
+        ));
+    }
+
+    #[test]
+    fn spans_count_bytes() {
+        let source = "€ x";
+        let spans = Lexer::new(source).map(|(_, span)| span).collect::<Vec<_>>();
+
+        assert_eq!(spans, [Range::from(0..3), Range::from(4..5)]);
+    }
+}
@@ -0,0 +1,46 @@
+//! The front end of the Invariant language: lexer, parser, AST, diagnostics
+//! and the tools built on them.
+//!
+//! ```
+//! let (program, errors) = invariant::parse("fn main() { return 1; }");
+//!
+//! assert!(errors.is_empty());
+//! assert_eq!(program.items()[0].name(), "main");
+//! ```
+//!
+//! Spans are byte ranges into the source the tree was parsed from.
+//! `diagnostics::Source` maps them back to lines and columns.
+
+// `std::range` is stable from 1.96; the gate keeps older nightlies building.
+#![allow(stable_features)]
+#![feature(new_range_api)]
+
+pub mod ast;
+pub mod build;
+pub mod check;
+pub mod diagnostics;
+pub mod format;
+pub mod interpret;
+pub mod lexer;
+pub mod manifest;
+pub mod parser;
+pub mod repl;
+pub mod serialize;
+pub mod symbol;
+pub mod syntax;
+pub mod token;
+mod traits;
+pub mod types;
+pub mod watch;
+
+pub use ast::Program;
+pub use diagnostics::{Diagnostic, Renderer, Source};
+pub use lexer::Lexer;
+pub use parser::{ParseError, Parser};
+pub use token::{Token, TokenKind};
+
+/// Parses `source` into a program, recovering from errors where possible.
+/// Parts that could not be parsed are `Error` nodes in the tree.
+pub fn parse(source: &str) -> (Program, Vec<ParseError>) {
+    Parser::new(Lexer::new(source)).parse()
+}
@@ -1,19 +1,712 @@
+//! The `invariant` command-line driver.
+
+// `std::range` is stable from 1.96; the gate keeps older nightlies building.
+#![allow(stable_features)]
 #![feature(new_range_api)]
 
-use std::{fs, range::Range};
+use invariant::ast::visit::{Visitor, walk_expression, walk_statement};
+use invariant::ast::{Expression, Statement, TreePrinter};
+use invariant::build::{Failure, Outcome, Project, ProjectError};
+use invariant::check::{CheckError, check};
+use invariant::interpret::Interpreter;
+use invariant::manifest;
+use invariant::repl::{self, Repl};
+use invariant::token::Token;
+use invariant::watch::Watcher;
+use invariant::{
+    Diagnostic, Lexer, ParseError, Program, Renderer, Source, format, serialize, syntax,
+};
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::range::Range;
+use std::rc::Rc;
+use std::{env, fmt, fs};
+
+const USAGE: &str = "\
+usage: invariant <command> [options] [FILE...]
+
+commands:
+    lex [--json] FILE         print the tokens of FILE with their positions
+    parse [--spans] FILE      print the syntax tree of FILE
+    check [--watch] [--input=ast-json] FILE...
+                              report errors without running anything
+    run [--watch] [--input=ast-json] FILE
+                              run the `main` function of FILE
+    fmt [--check] FILE...     format files in place, or only list unformatted ones
+    repl                      evaluate code interactively
+    new NAME                  create a project in the directory NAME
+    build                     build the project around the current directory
+    emit --format=FORMAT [--spans] FILE
+                              print FILE as tokens, tokens-json, tokens-sexp,
+                              ast, ast-json, ast-sexp or cst; there is no
+                              IR yet, so there is no `ir` format
+
+A FILE of `-` is read from standard input. Inside a project, `check` without
+files checks all of its sources and `run` without a file runs its entry.
+With `--watch`, `check` and `run` start over whenever one of the files
+changes. With `--input=ast-json`, they read the trees that `emit
+--format=ast-json` and `build` write instead of source.
+
+options:
+    -h, --help                print this help
+    -V, --version             print the version
+
+exit status:
+    0  success
+    1  the input has errors, or is not formatted
+    2  the command line is invalid, or a file could not be read or written
+    3  the program failed while running
+";
+
+/// The input has errors, or `fmt --check` found unformatted files.
+const DIAGNOSTICS: u8 = 1;
+/// The command line is invalid, or a file could not be read or written.
+const INVALID_USE: u8 = 2;
+/// The program failed while running.
+const RUNTIME_FAILURE: u8 = 3;
+
+/// Moves the cursor to the top left corner of a cleared terminal.
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// The name diagnostics use for standard input.
+const STDIN: &str = "<stdin>";
+
+fn main() -> ExitCode {
+    let mut arguments = env::args().skip(1);
+    let Some(command) = arguments.next() else {
+        eprint!("{USAGE}");
+        return ExitCode::from(INVALID_USE);
+    };
+    let arguments = arguments.collect::<Vec<_>>();
+
+    let result = match command.as_str() {
+        "lex" => lex(arguments),
+        "parse" => parse(arguments),
+        "check" => check_files(arguments),
+        "run" => run(arguments),
+        "fmt" => fmt(arguments),
+        "emit" => emit(arguments),
+        "repl" => repl(arguments),
+        "new" => new(arguments),
+        "build" => build(arguments),
+        "-h" | "--help" | "help" => output(USAGE),
+        "-V" | "--version" | "version" => {
+            output(format_args!("invariant {}\n", env!("CARGO_PKG_VERSION")))
+        }
+        _ => Err(invalid_use(&format!("unknown command `{command}`"))),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(status) => ExitCode::from(status),
+    }
+}
+
+/// Reports a mistake on the command line and returns the matching status.
+fn invalid_use(message: &str) -> u8 {
+    eprintln!("error: {message}");
+    eprintln!("see `invariant --help`");
+
+    INVALID_USE
+}
+
+/// Splits the arguments of a command into options and paths. Every option
+/// must be one of `options`, or start with one of them that ends in `=`.
+fn split_arguments(
+    command: &str,
+    arguments: Vec<String>,
+    options: &[&str],
+) -> Result<(Vec<String>, Vec<String>), u8> {
+    let mut flags = Vec::new();
+    let mut paths = Vec::new();
+
+    for argument in arguments {
+        if argument == "-" || !argument.starts_with('-') {
+            paths.push(argument);
+        } else if options.iter().any(|option| {
+            argument == *option || (option.ends_with('=') && argument.starts_with(option))
+        }) {
+            flags.push(argument);
+        } else {
+            return Err(invalid_use(&format!(
+                "unknown option `{argument}` for `{command}`"
+            )));
+        }
+    }
+
+    Ok((flags, paths))
+}
+
+/// Returns the path of a command that takes exactly one file.
+fn single_path(command: &str, paths: Vec<String>) -> Result<String, u8> {
+    match <[String; 1]>::try_from(paths) {
+        Ok([path]) => Ok(path),
+        Err(paths) if paths.is_empty() => Err(invalid_use(&format!("`{command}` needs a file"))),
+        Err(_) => Err(invalid_use(&format!("`{command}` takes a single file"))),
+    }
+}
+
+/// Reads a file, or standard input for `-`. Returns the name diagnostics
+/// should use for it together with its contents.
+fn read(path: &str) -> Result<(&str, String), u8> {
+    let result = if path == "-" {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input).map(|_| input)
+    } else {
+        fs::read_to_string(path)
+    };
+
+    match result {
+        Ok(input) => Ok((if path == "-" { STDIN } else { path }, input)),
+        Err(error) => {
+            eprintln!("error: could not read `{path}`: {error}");
+            Err(INVALID_USE)
+        }
+    }
+}
+
+/// Writes `text` to standard output. A reader that stops reading early, like
+/// `head`, is not an error.
+fn output(text: impl std::fmt::Display) -> Result<(), u8> {
+    let mut stdout = io::stdout().lock();
+    match write!(stdout, "{text}").and_then(|()| stdout.flush()) {
+        Err(error) if error.kind() != io::ErrorKind::BrokenPipe => {
+            eprintln!("error: could not write to standard output: {error}");
+            Err(INVALID_USE)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn report(source: &Source, diagnostics: impl IntoIterator<Item = Diagnostic>) {
+    let renderer = Renderer::stderr();
+    for diagnostic in diagnostics {
+        eprintln!("{}", renderer.render(&diagnostic, source));
+    }
+}
+
+fn report_parse_errors(source: &Source, errors: &[ParseError]) {
+    report(
+        source,
+        errors
+            .iter()
+            .map(|error| Diagnostic::from_parse_error(error, source)),
+    );
+}
+
+fn report_check_errors(source: &Source, errors: &[CheckError]) {
+    report(source, errors.iter().map(Diagnostic::from_check_error));
+}
+
+/// Reports errors in a tree that was read without its source, whose spans
+/// cannot be shown.
+fn report_tree_errors(name: &str, messages: impl IntoIterator<Item = String>) {
+    report(
+        &Source::new(name, ""),
+        messages.into_iter().map(|message| {
+            Diagnostic::error(message).with_note(format!("in the tree read from `{name}`"))
+        }),
+    );
+}
+
+/// Parses a file, reporting its syntax errors.
+fn parse_file(name: &str, input: &str) -> Result<Program, u8> {
+    let (program, errors) = invariant::parse(input);
+    if !errors.is_empty() {
+        report_parse_errors(&Source::new(name, input), &errors);
+        return Err(DIAGNOSTICS);
+    }
+
+    Ok(program)
+}
+
+/// Parses and checks a file, reporting every error. Only returns the program
+/// if there are none.
+fn check_file(name: &str, input: &str) -> Result<Program, u8> {
+    let program = parse_file(name, input)?;
+
+    let (_, errors) = check(&program);
+    if !errors.is_empty() {
+        report_check_errors(&Source::new(name, input), &errors);
+        return Err(DIAGNOSTICS);
+    }
+
+    Ok(program)
+}
+
+/// What `check` and `run` read their files as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum InputFormat {
+    Source,
+    /// The JSON encoding of the AST, see `serialize::program_from_json`.
+    AstJson,
+}
+
+fn input_format(flags: &[String]) -> Result<InputFormat, u8> {
+    match flags
+        .iter()
+        .rev()
+        .find_map(|flag| flag.strip_prefix("--input="))
+    {
+        None | Some("source") => Ok(InputFormat::Source),
+        Some("ast-json") => Ok(InputFormat::AstJson),
+        Some(format) => Err(invalid_use(&format!("unknown input format `{format}`"))),
+    }
+}
+
+/// Reads the program in a file in `format` and checks it, reporting every
+/// error. Only returns the program if there are none.
+fn load_file(name: &str, input: &str, format: InputFormat) -> Result<Program, u8> {
+    if format == InputFormat::Source {
+        return check_file(name, input);
+    }
+
+    let program = serialize::program_from_json_str(input).map_err(|error| {
+        report_tree_errors(name, [format!("not a valid AST: {error}")]);
+        DIAGNOSTICS
+    })?;
+    let mut error_nodes = ErrorNodes::default();
+    error_nodes.visit_program(&program);
+    if !error_nodes.0.is_empty() {
+        report_tree_errors(
+            name,
+            error_nodes.0.iter().map(|span| {
+                format!(
+                    "the tree has an error node at bytes {}..{}",
+                    span.start, span.end
+                )
+            }),
+        );
+        return Err(DIAGNOSTICS);
+    }
+
+    let (_, errors) = check(&program);
+    if !errors.is_empty() {
+        report_tree_errors(name, errors.iter().map(ToString::to_string));
+        return Err(DIAGNOSTICS);
+    }
+
+    Ok(program)
+}
+
+/// Collects the spans of the error nodes in a tree, which the parser leaves
+/// where it recovered. A tree read back with them cannot run.
+#[derive(Default)]
+struct ErrorNodes(Vec<Range<usize>>);
+
+impl Visitor for ErrorNodes {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Error { span, .. } => self.0.push(*span),
+            _ => walk_statement(self, statement),
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Error { span, .. } => self.0.push(*span),
+            _ => walk_expression(self, expression),
+        }
+    }
+}
+
+fn tokens(input: &str) -> Vec<(Token, Range<usize>)> {
+    Lexer::new(input).collect()
+}
+
+fn lex(arguments: Vec<String>) -> Result<(), u8> {
+    let (flags, paths) = split_arguments("lex", arguments, &["--json"])?;
+    let (_, input) = read(&single_path("lex", paths)?)?;
+
+    if flags.iter().any(|flag| flag == "--json") {
+        output(format_args!(
+            "{:#}\n",
+            serialize::tokens_to_json(&input, &tokens(&input))
+        ))
+    } else {
+        output(serialize::tokens_to_text(&input, &tokens(&input)))
+    }
+}
+
+fn parse(arguments: Vec<String>) -> Result<(), u8> {
+    let (flags, paths) = split_arguments("parse", arguments, &["--spans"])?;
+    let path = single_path("parse", paths)?;
+    let (name, input) = read(&path)?;
+
+    let program = parse_file(name, &input)?;
+    let spans = flags.iter().any(|flag| flag == "--spans");
+    output(TreePrinter::new(spans).print_program(&program))
+}
+
+/// Checks every file, so one run reports the errors of all of them.
+fn check_files(arguments: Vec<String>) -> Result<(), u8> {
+    let (flags, paths) = split_arguments("check", arguments, &["--watch", "--input="])?;
+    let format = input_format(&flags)?;
+    let files = if paths.is_empty() {
+        Files::Project(Rc::new(current_project("`check` needs a file")?))
+    } else {
+        Files::Named(paths)
+    };
+
+    let check = |paths: &[String]| {
+        let mut result = Ok(());
+        for path in paths {
+            if let Err(status) =
+                read(path).and_then(|(name, input)| load_file(name, &input, format))
+            {
+                result = Err(status);
+            }
+        }
+
+        result
+    };
+
+    if flags.iter().any(|flag| flag == "--watch") {
+        watch(&files, check)
+    } else {
+        check(&files.list()?)
+    }
+}
+
+fn run(arguments: Vec<String>) -> Result<(), u8> {
+    let (flags, mut paths) = split_arguments("run", arguments, &["--watch", "--input="])?;
+    let format = input_format(&flags)?;
+    if paths.is_empty() {
+        let project = current_project("`run` needs a file")?;
+        paths = project_paths(&project, Ok(vec![project.manifest.entry.clone()]))?;
+    }
+    let path = single_path("run", paths)?;
+
+    if flags.iter().any(|flag| flag == "--watch") {
+        watch(&Files::Named(vec![path.clone()]), |_| {
+            run_file(&path, format)
+        })
+    } else {
+        run_file(&path, format)
+    }
+}
+
+fn run_file(path: &str, format: InputFormat) -> Result<(), u8> {
+    let (name, input) = read(path)?;
+    let program = load_file(name, &input, format)?;
+
+    let mut interpreter = Interpreter::new(io::stdout());
+    interpreter.load(program);
+
+    match interpreter.run() {
+        Ok(_) => Ok(()),
+        Err(error) => {
+            match format {
+                InputFormat::Source => report(
+                    &Source::new(name, &input),
+                    [Diagnostic::from_runtime_error(&error)],
+                ),
+                InputFormat::AstJson => report_tree_errors(name, [error.to_string()]),
+            }
+            Err(RUNTIME_FAILURE)
+        }
+    }
+}
+
+/// The files a command works on: the ones it was given, or the sources of
+/// the current project, which are listed again every time.
+#[derive(Clone)]
+enum Files {
+    Named(Vec<String>),
+    Project(Rc<Project>),
+}
+
+impl Files {
+    fn paths(&self) -> Result<Vec<PathBuf>, ProjectError> {
+        match self {
+            Files::Named(paths) => Ok(paths.iter().map(PathBuf::from).collect()),
+            Files::Project(project) => Ok(project
+                .sources()?
+                .iter()
+                .map(|path| project.root.join(path))
+                .collect()),
+        }
+    }
+
+    /// The paths, in the form the commands read.
+    fn list(&self) -> Result<Vec<String>, u8> {
+        let paths = self.paths().map_err(project_error)?;
+
+        Ok(paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect())
+    }
+}
+
+impl fmt::Display for Files {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Files::Named(paths) => f.write_str(&paths.join(", ")),
+            Files::Project(_) => f.write_str("the sources of the project"),
+        }
+    }
+}
+
+/// Runs `action` on the files, then again after every change to them,
+/// clearing the screen each time. Only returns if it cannot watch.
+fn watch(files: &Files, mut action: impl FnMut(&[String]) -> Result<(), u8>) -> Result<(), u8> {
+    if let Files::Named(paths) = files
+        && paths.iter().any(|path| path == "-")
+    {
+        return Err(invalid_use("`--watch` cannot watch standard input"));
+    }
+
+    let mut watcher = Watcher::listing({
+        let files = files.clone();
+        // a project that cannot be listed is listed again on the next poll
+        move || files.paths().unwrap_or_default()
+    });
+    let terminal = io::stdout().is_terminal();
+    loop {
+        if terminal {
+            print!("{CLEAR_SCREEN}");
+        }
+
+        let outcome = match files.list().and_then(|paths| action(&paths)) {
+            Ok(()) => "succeeded",
+            Err(_) => "failed",
+        };
+        println!("\n[{outcome}] watching {files}, press Ctrl-C to stop");
+        // a failed flush only loses the status line
+        let _ = io::stdout().flush();
+
+        watcher.wait();
+    }
+}
+
+/// Formats every file in place, or with `--check` only reports the files that
+/// are not formatted. Standard input is formatted to standard output.
+fn fmt(arguments: Vec<String>) -> Result<(), u8> {
+    let (flags, paths) = split_arguments("fmt", arguments, &["--check"])?;
+    if paths.is_empty() {
+        return Err(invalid_use("`fmt` needs a file"));
+    }
+    let check = flags.iter().any(|flag| flag == "--check");
+
+    let mut result = Ok(());
+    for path in paths {
+        let (name, input) = match read(&path) {
+            Ok(file) => file,
+            Err(status) => {
+                result = Err(status);
+                continue;
+            }
+        };
+
+        let formatted = match format::format(&input) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                report_parse_errors(&Source::new(name, &input), &errors);
+                result = Err(DIAGNOSTICS);
+                continue;
+            }
+        };
+
+        if check {
+            if formatted != input {
+                output(format_args!("`{name}` is not formatted\n"))?;
+                result = Err(DIAGNOSTICS);
+            }
+        } else if path == "-" {
+            output(formatted)?;
+        } else if formatted != input
+            && let Err(error) = fs::write(&path, formatted)
+        {
+            eprintln!("error: could not write `{path}`: {error}");
+            result = Err(INVALID_USE);
+        }
+    }
+
+    result
+}
+
+fn emit(arguments: Vec<String>) -> Result<(), u8> {
+    let (flags, paths) = split_arguments("emit", arguments, &["--format=", "--spans"])?;
+    let spans = flags.iter().any(|flag| flag == "--spans");
+    let Some(format) = flags
+        .iter()
+        .rev()
+        .find_map(|flag| flag.strip_prefix("--format="))
+    else {
+        return Err(invalid_use("`emit` needs a `--format=FORMAT`"));
+    };
+    if !matches!(
+        format,
+        "tokens" | "tokens-json" | "tokens-sexp" | "ast" | "ast-json" | "ast-sexp" | "cst"
+    ) {
+        return Err(invalid_use(&match format {
+            "ir" => "there is no IR yet, so `emit` has no `ir` format".to_string(),
+            _ => format!("unknown format `{format}`"),
+        }));
+    }
+
+    let path = single_path("emit", paths)?;
+    let (name, input) = read(&path)?;
+
+    match format {
+        "tokens" => output(serialize::tokens_to_text(&input, &tokens(&input))),
+        "tokens-json" => output(format_args!(
+            "{:#}\n",
+            serialize::tokens_to_json(&input, &tokens(&input))
+        )),
+        "tokens-sexp" => output(serialize::tokens_to_sexp(&input, &tokens(&input))),
+        "ast" => output(TreePrinter::new(spans).print_program(&parse_file(name, &input)?)),
+        "ast-json" => output(format_args!(
+            "{:#}\n",
+            serialize::program_to_json(&parse_file(name, &input)?)
+        )),
+        "ast-sexp" => output(serialize::program_to_sexp(&parse_file(name, &input)?)),
+        _ => output(syntax::parse(&input).debug_tree()),
+    }
+}
+
+fn repl(arguments: Vec<String>) -> Result<(), u8> {
+    let (_, paths) = split_arguments("repl", arguments, &[])?;
+    if !paths.is_empty() {
+        return Err(invalid_use("`repl` does not take files"));
+    }
+
+    read_evaluate_print().map_err(|error| {
+        eprintln!("error: {error}");
+        INVALID_USE
+    })
+}
+
+/// Reads inputs from standard input until it ends or `:quit` is entered. An
+/// input with an unclosed `{` continues on the next line.
+fn read_evaluate_print() -> io::Result<()> {
+    let mut repl = Repl::new(io::stdout(), Renderer::stdout());
+    let mut lines = io::stdin().lock().lines();
+    println!(
+        "invariant {}, `:help` lists the commands",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let mut input = String::new();
+    loop {
+        print!("{}", if input.is_empty() { ">> " } else { ".. " });
+        io::stdout().flush()?;
+
+        let Some(line) = lines.next().transpose()? else {
+            println!();
+            return Ok(());
+        };
+        input.push_str(&line);
+        input.push('\n');
+        if !repl::is_complete(&input) {
+            continue;
+        }
+
+        if !repl.handle(&std::mem::take(&mut input))? {
+            return Ok(());
+        }
+    }
+}
+
+/// The project around the current directory. Without one, fails with
+/// `message` as a usage error.
+fn current_project(message: &str) -> Result<Project, u8> {
+    match Project::discover(Path::new("")) {
+        Ok(project) => Ok(project),
+        Err(ProjectError::NotFound) => Err(invalid_use(&format!(
+            "{message}, or an `{}` in this directory or a parent",
+            manifest::FILE_NAME
+        ))),
+        Err(error) => Err(project_error(error)),
+    }
+}
+
+fn project_error(error: ProjectError) -> u8 {
+    eprintln!("error: {error}");
+
+    INVALID_USE
+}
+
+/// Turns paths relative to the project root into paths the commands can read.
+fn project_paths(
+    project: &Project,
+    paths: Result<Vec<PathBuf>, ProjectError>,
+) -> Result<Vec<String>, u8> {
+    let paths = paths.map_err(project_error)?;
+
+    Ok(paths
+        .iter()
+        .map(|path| project.root.join(path).display().to_string())
+        .collect())
+}
+
+fn new(arguments: Vec<String>) -> Result<(), u8> {
+    let (_, paths) = split_arguments("new", arguments, &[])?;
+    let name = single_path("new", paths)?;
+
+    let project = Project::create(Path::new(""), &name).map_err(project_error)?;
+    println!(
+        "created the project `{name}` in `{}`",
+        project.root.display()
+    );
+
+    Ok(())
+}
+
+/// Builds every source of the project and reports the diagnostics of the
+/// ones that fail.
+fn build(arguments: Vec<String>) -> Result<(), u8> {
+    let (_, paths) = split_arguments("build", arguments, &[])?;
+    if !paths.is_empty() {
+        return Err(invalid_use(
+            "`build` builds the whole project and takes no files",
+        ));
+    }
+
+    let project = current_project("`build` needs a project")?;
+    let units = project.build().map_err(project_error)?;
+
+    let (mut built, mut fresh, mut failed) = (0, 0, 0);
+    for unit in &units {
+        let path = project.root.join(&unit.source);
+        let name = path.display().to_string();
+        let source = Source::new(&name, &unit.text);
 
-mod ast;
-mod lexer;
-mod parser;
-mod token;
-mod traits;
+        match &unit.outcome {
+            Outcome::Fresh => fresh += 1,
+            Outcome::Built(_) => {
+                println!("built {name}");
+                built += 1;
+            }
+            Outcome::Failed(Failure::Parse(errors)) => {
+                report_parse_errors(&source, errors);
+                failed += 1;
+            }
+            Outcome::Failed(Failure::Check(errors)) => {
+                report_check_errors(&source, errors);
+                failed += 1;
+            }
+        }
+    }
 
-use lexer::Lexer;
-use token::Token;
+    let manifest = &project.manifest;
+    if failed > 0 {
+        eprintln!(
+            "error: could not build `{}`, {failed} of {} files have errors",
+            manifest.name,
+            units.len()
+        );
+        return Err(DIAGNOSTICS);
+    }
 
-fn main() {
-    let input = fs::read_to_string("./examples/example1.inv").unwrap();
-    let tokens = Lexer::new(&input).collect::<Vec<(Token, Range<usize>)>>();
+    println!(
+        "finished {} v{}: {built} built, {fresh} up to date, in `{}`",
+        manifest.name,
+        manifest.version,
+        project.target_directory().display()
+    );
 
-    println!("{:?}", tokens)
+    Ok(())
 }
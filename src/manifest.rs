@@ -0,0 +1,391 @@
+//! `Invariant.toml`, the manifest at the root of a project.
+//!
+//! ```toml
+//! [package]
+//! name = "hello"
+//! version = "0.1.0"
+//! entry = "src/main.inv"
+//!
+//! [build]
+//! sources = ["src"]
+//! format = "json"
+//! ```
+//!
+//! Only `name` is required. Paths are relative to the directory of the
+//! manifest and may not leave it. Unknown tables and keys are errors, so that
+//! typos do not go unnoticed.
+
+pub mod toml;
+
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use toml::{Entry, Table, Value};
+
+pub const FILE_NAME: &str = "Invariant.toml";
+
+/// How `invariant build` writes the tree of every source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactFormat {
+    /// The JSON encoding of `serialize::program_to_json`, which can be read
+    /// back.
+    Json,
+    /// The S-expressions of `serialize::program_to_sexp`.
+    Sexp,
+}
+
+impl ArtifactFormat {
+    pub const ALL: [ArtifactFormat; 2] = [ArtifactFormat::Json, ArtifactFormat::Sexp];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ArtifactFormat::Json => "json",
+            ArtifactFormat::Sexp => "sexp",
+        }
+    }
+
+    /// The extension of artifacts in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArtifactFormat::Json => "ast.json",
+            ArtifactFormat::Sexp => "ast.sexp",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub name: String,
+    pub version: String,
+    /// The file `invariant run` starts, which is built even if it lies
+    /// outside of `sources`.
+    pub entry: PathBuf,
+    /// The directories whose `.inv` files are built.
+    pub sources: Vec<PathBuf>,
+    pub format: ArtifactFormat,
+}
+
+/// An invalid manifest. `line` is zero for problems that are not on any one
+/// line, like a missing key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            0 => f.write_str(&self.message),
+            line => write!(f, "line {line}: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+impl From<toml::TomlError> for ManifestError {
+    fn from(error: toml::TomlError) -> Self {
+        Self {
+            line: error.line,
+            message: error.message,
+        }
+    }
+}
+
+impl Manifest {
+    /// The manifest of a new project, with every other setting at its
+    /// default.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: "0.1.0".to_string(),
+            entry: PathBuf::from("src/main.inv"),
+            sources: vec![PathBuf::from("src")],
+            format: ArtifactFormat::Json,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ManifestError> {
+        let mut manifest = Manifest::new(String::new());
+        let mut name = None;
+
+        for Table {
+            name: table,
+            line,
+            entries,
+        } in toml::parse(text)?
+        {
+            match table.as_str() {
+                "" => {
+                    if let Some(entry) = entries.first() {
+                        return Err(error(
+                            entry.line,
+                            format!("`{}` must be inside a table", entry.key),
+                        ));
+                    }
+                }
+                "package" => {
+                    for entry in entries {
+                        match entry.key.as_str() {
+                            "name" => name = Some(string(entry)?),
+                            "version" => manifest.version = string(entry)?,
+                            "entry" => manifest.entry = path(entry)?,
+                            _ => return Err(unknown_key(&table, &entry)),
+                        }
+                    }
+                }
+                "build" => {
+                    for entry in entries {
+                        match entry.key.as_str() {
+                            "sources" => manifest.sources = paths(entry)?,
+                            "format" => manifest.format = format(entry)?,
+                            _ => return Err(unknown_key(&table, &entry)),
+                        }
+                    }
+                }
+                _ => return Err(error(line, format!("unknown table `[{table}]`"))),
+            }
+        }
+
+        manifest.name = name.ok_or_else(|| error(0, "`[package]` needs a `name`"))?;
+        if !is_valid_name(&manifest.name) {
+            return Err(error(
+                0,
+                format!("`{}` is not a valid package name", manifest.name),
+            ));
+        }
+
+        Ok(manifest)
+    }
+
+    /// Writes the manifest back as TOML that `parse` accepts.
+    pub fn to_toml(&self) -> String {
+        let quote = |string: &str| format!("{string:?}");
+        let sources = self
+            .sources
+            .iter()
+            .map(|source| quote(&source.to_string_lossy()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(
+            "[package]\nname = {}\nversion = {}\nentry = {}\n\n[build]\nsources = [{sources}]\nformat = {}\n",
+            quote(&self.name),
+            quote(&self.version),
+            quote(&self.entry.to_string_lossy()),
+            quote(self.format.name()),
+        )
+    }
+}
+
+/// `path` without its `.` components, or `None` if it is absolute or has a
+/// `..` component, which could point outside of the project.
+pub fn relative_path(path: &Path) -> Option<PathBuf> {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .map(|component| match component {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Package names start with a letter and continue with letters, digits, `-`
+/// and `_`.
+pub fn is_valid_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn error(line: usize, message: impl Into<String>) -> ManifestError {
+    ManifestError {
+        line,
+        message: message.into(),
+    }
+}
+
+fn unknown_key(table: &str, entry: &Entry) -> ManifestError {
+    error(
+        entry.line,
+        format!("unknown key `{}` in `[{table}]`", entry.key),
+    )
+}
+
+fn mismatch(entry: &Entry, expected: &str, found: &Value) -> ManifestError {
+    error(
+        entry.line,
+        format!(
+            "`{}` must be {expected}, not a {}",
+            entry.key,
+            found.type_name()
+        ),
+    )
+}
+
+fn string(entry: Entry) -> Result<String, ManifestError> {
+    match entry.value {
+        Value::String(string) => Ok(string),
+        ref value => Err(mismatch(&entry, "a string", value)),
+    }
+}
+
+fn project_path(entry: &Entry, path: &str) -> Result<PathBuf, ManifestError> {
+    relative_path(Path::new(path)).ok_or_else(|| {
+        error(
+            entry.line,
+            format!(
+                "`{path}` in `{}` is not a relative path inside the project",
+                entry.key
+            ),
+        )
+    })
+}
+
+/// The path of a file. Unlike a directory, it cannot be the project root.
+fn path(entry: Entry) -> Result<PathBuf, ManifestError> {
+    match &entry.value {
+        Value::String(path) => match project_path(&entry, path)? {
+            file if file.as_os_str().is_empty() => Err(error(
+                entry.line,
+                format!("`{path}` in `{}` does not name a file", entry.key),
+            )),
+            file => Ok(file),
+        },
+        value => Err(mismatch(&entry, "a string", value)),
+    }
+}
+
+fn paths(entry: Entry) -> Result<Vec<PathBuf>, ManifestError> {
+    let Value::Array(values) = &entry.value else {
+        return Err(mismatch(&entry, "an array of strings", &entry.value));
+    };
+
+    values
+        .iter()
+        .map(|value| match value {
+            Value::String(path) => project_path(&entry, path),
+            value => Err(mismatch(&entry, "an array of strings", value)),
+        })
+        .collect()
+}
+
+fn format(entry: Entry) -> Result<ArtifactFormat, ManifestError> {
+    let line = entry.line;
+    let name = string(entry)?;
+
+    ArtifactFormat::ALL
+        .into_iter()
+        .find(|format| format.name() == name)
+        .ok_or_else(|| {
+            error(
+                line,
+                format!("unknown format `{name}`, expected `json` or `sexp`"),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> String {
+        Manifest::parse(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn fills_in_defaults() {
+        assert_eq!(
+            Manifest::parse("[package]\nname = \"hello\"\n"),
+            Ok(Manifest::new("hello"))
+        );
+
+        let manifest = Manifest::parse(
+            "[package]\nname = \"app\"\nentry = \"bin/app.inv\"\n\
+             [build]\nsources = [\"lib\", \"bin\"]\nformat = \"sexp\"\n",
+        )
+        .unwrap();
+        assert_eq!(manifest.entry, PathBuf::from("bin/app.inv"));
+        assert_eq!(
+            manifest.sources,
+            [PathBuf::from("lib"), PathBuf::from("bin")]
+        );
+        assert_eq!(manifest.format, ArtifactFormat::Sexp);
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let mut manifest = Manifest::new("round-trip");
+        manifest.sources.push(PathBuf::from("more sources"));
+
+        assert_eq!(Manifest::parse(&manifest.to_toml()), Ok(manifest));
+    }
+
+    #[test]
+    fn rejects_invalid_manifests() {
+        assert_eq!(error("[build]\n"), "`[package]` needs a `name`");
+        assert_eq!(
+            error("[package]\nname = \"1st\""),
+            "`1st` is not a valid package name"
+        );
+        assert_eq!(
+            error("[package]\nname = \"a\"\nnmae = \"b\""),
+            "line 3: unknown key `nmae` in `[package]`"
+        );
+        assert_eq!(
+            error("[dependencies]\n"),
+            "line 1: unknown table `[dependencies]`"
+        );
+        assert_eq!(
+            error("[package]\nname = \"a\"\n[build]\nsources = \"src\""),
+            "line 4: `sources` must be an array of strings, not a string"
+        );
+        assert_eq!(
+            error("[package]\nname = \"a\"\n[build]\nformat = \"xml\""),
+            "line 4: unknown format `xml`, expected `json` or `sexp`"
+        );
+        assert_eq!(
+            error("name = \"a\""),
+            "line 1: `name` must be inside a table"
+        );
+    }
+
+    #[test]
+    fn keeps_paths_inside_the_project() {
+        let manifest = Manifest::parse(
+            "[package]\nname = \"a\"\nentry = \"./src/./main.inv\"\n\
+             [build]\nsources = [\"./src\", \"lib/\"]\n",
+        )
+        .unwrap();
+        assert_eq!(manifest.entry, PathBuf::from("src/main.inv"));
+        assert_eq!(
+            manifest.sources,
+            [PathBuf::from("src"), PathBuf::from("lib")]
+        );
+
+        assert_eq!(
+            error("[package]\nname = \"a\"\nentry = \"../shared/lib.inv\""),
+            "line 3: `../shared/lib.inv` in `entry` is not a relative path inside the project"
+        );
+        assert_eq!(
+            error("[package]\nname = \"a\"\nentry = \"/tmp/main.inv\""),
+            "line 3: `/tmp/main.inv` in `entry` is not a relative path inside the project"
+        );
+        assert_eq!(
+            error("[package]\nname = \"a\"\n[build]\nsources = [\"src\", \"src/../..\"]"),
+            "line 4: `src/../..` in `sources` is not a relative path inside the project"
+        );
+        assert_eq!(
+            error("[package]\nname = \"a\"\nentry = \"\""),
+            "line 3: `` in `entry` does not name a file"
+        );
+        assert_eq!(
+            error("[package]\nname = \"a\"\nentry = \"./\""),
+            "line 3: `./` in `entry` does not name a file"
+        );
+        let manifest =
+            Manifest::parse("[package]\nname = \"a\"\n[build]\nsources = [\".\"]").unwrap();
+        assert_eq!(manifest.sources, [PathBuf::new()]);
+    }
+}
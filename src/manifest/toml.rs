@@ -0,0 +1,374 @@
+//! The subset of TOML that manifests need: `[table]` headers and
+//! `key = value` pairs, whose values are basic strings, integers, booleans
+//! or arrays of them. Arrays may span several lines. Dotted keys, inline
+//! tables, literal strings, floats and dates are not supported.
+
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    /// The name of the value's type, for error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Integer(_) => "integer",
+            Value::Boolean(_) => "boolean",
+            Value::Array(_) => "array",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+}
+
+/// A table and the entries that follow its header. The entries in front of
+/// the first header belong to the root table, whose name is empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub name: String,
+    pub line: usize,
+    pub entries: Vec<Entry>,
+}
+
+/// A syntax error, together with the one-based line it is on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TomlError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TomlError {}
+
+/// Parses a document into its tables, in the order they appear.
+pub fn parse(text: &str) -> Result<Vec<Table>, TomlError> {
+    let mut parser = Parser {
+        chars: text.chars().peekable(),
+        line: 1,
+    };
+    let mut tables = vec![Table {
+        name: String::new(),
+        line: 1,
+        entries: Vec::new(),
+    }];
+
+    loop {
+        parser.skip_blank_lines();
+        match parser.chars.peek() {
+            None => return Ok(tables),
+            Some('[') => {
+                let line = parser.line;
+                parser.chars.next();
+                parser.skip_whitespace();
+                let name = parser.key()?;
+                parser.skip_whitespace();
+                parser.expect(']')?;
+                parser.end_of_line()?;
+
+                if tables.iter().any(|table| table.name == name) {
+                    return Err(TomlError {
+                        line,
+                        message: format!("the table `{name}` is defined twice"),
+                    });
+                }
+                tables.push(Table {
+                    name,
+                    line,
+                    entries: Vec::new(),
+                });
+            }
+            Some(_) => {
+                let line = parser.line;
+                let key = parser.key()?;
+                parser.skip_whitespace();
+                parser.expect('=')?;
+                parser.skip_whitespace();
+                let value = parser.value()?;
+                parser.end_of_line()?;
+
+                let table = tables.last_mut().expect("there is always a table");
+                if table.entries.iter().any(|entry| entry.key == key) {
+                    return Err(TomlError {
+                        line,
+                        message: format!("the key `{key}` is defined twice"),
+                    });
+                }
+                table.entries.push(Entry { key, value, line });
+            }
+        }
+    }
+}
+
+struct Parser<'t> {
+    chars: Peekable<Chars<'t>>,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: impl Into<String>) -> TomlError {
+        TomlError {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+        }
+
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), TomlError> {
+        match self.chars.peek() {
+            Some(&c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            Some(&c) => Err(self.error(format!("expected `{expected}`, found `{c}`"))),
+            None => Err(self.error(format!("expected `{expected}`, found the end of the file"))),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|&c| c == ' ' || c == '\t').is_some() {}
+    }
+
+    fn skip_comment(&mut self) {
+        if self.chars.peek() == Some(&'#') {
+            while self.chars.next_if(|&c| c != '\n').is_some() {}
+        }
+    }
+
+    /// Skips whitespace, comments and line breaks.
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_whitespace();
+            self.skip_comment();
+            match self.chars.peek() {
+                Some('\n') => {
+                    self.next();
+                }
+                Some('\r') => {
+                    self.chars.next();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Only whitespace and a comment may follow a header or a value.
+    fn end_of_line(&mut self) -> Result<(), TomlError> {
+        self.skip_whitespace();
+        self.skip_comment();
+        self.chars.next_if_eq(&'\r');
+        match self.chars.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.next();
+                Ok(())
+            }
+            Some(&c) => Err(self.error(format!("expected the end of the line, found `{c}`"))),
+        }
+    }
+
+    fn key(&mut self) -> Result<String, TomlError> {
+        let mut key = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|&c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            key.push(c);
+        }
+
+        match self.chars.peek() {
+            _ if !key.is_empty() => Ok(key),
+            Some(&c) => Err(self.error(format!("expected a key, found `{c}`"))),
+            None => Err(self.error("expected a key, found the end of the file")),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, TomlError> {
+        match self.chars.peek() {
+            Some('"') => self.string().map(Value::String),
+            Some('[') => self.array(),
+            Some(c) if c.is_ascii_digit() || *c == '-' || *c == '+' => self.integer(),
+            Some(c) if c.is_ascii_alphabetic() => {
+                let word = self.key()?;
+                match word.as_str() {
+                    "true" => Ok(Value::Boolean(true)),
+                    "false" => Ok(Value::Boolean(false)),
+                    _ => Err(self.error(format!(
+                        "expected a value, found `{word}`; strings need quotes"
+                    ))),
+                }
+            }
+            Some(&c) => Err(self.error(format!("expected a value, found `{c}`"))),
+            None => Err(self.error("expected a value, found the end of the file")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, TomlError> {
+        self.expect('"')?;
+        let mut string = String::new();
+
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => string.push(match self.chars.next() {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some(c) => return Err(self.error(format!("unknown escape `\\{c}`"))),
+                    None => return Err(self.error("unterminated string")),
+                }),
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Result<Value, TomlError> {
+        let mut digits = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|&c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+' | '.'))
+        {
+            if c != '_' {
+                digits.push(c);
+            }
+        }
+
+        digits
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| self.error(format!("`{digits}` is not a valid integer")))
+    }
+
+    /// Parses `[value, ...]`, which may spread over several lines and end
+    /// with a trailing comma.
+    fn array(&mut self) -> Result<Value, TomlError> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+
+        loop {
+            self.skip_blank_lines();
+            if self.chars.next_if_eq(&']').is_some() {
+                return Ok(Value::Array(values));
+            }
+
+            values.push(self.value()?);
+            self.skip_blank_lines();
+            if self.chars.next_if_eq(&',').is_none() {
+                self.skip_blank_lines();
+                self.expect(']')?;
+                return Ok(Value::Array(values));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> String {
+        parse(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_tables_and_values() {
+        let tables = parse(
+            "top = 1 # comment\n\
+             \n\
+             [package]\n\
+             name = \"a \\\"quoted\\\" name\"\n\
+             count = -1_000\n\
+             [build]\r\n\
+             flags = [true, false]\n\
+             sources = [\n  \"src\", # the default\n  \"lib\",\n]\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            tables
+                .iter()
+                .map(|table| (table.name.as_str(), table.line))
+                .collect::<Vec<_>>(),
+            [("", 1), ("package", 3), ("build", 6)]
+        );
+        assert_eq!(
+            tables[0].entries,
+            [Entry {
+                key: "top".to_string(),
+                value: Value::Integer(1),
+                line: 1
+            }]
+        );
+        assert_eq!(
+            tables[1].entries[0].value,
+            Value::String("a \"quoted\" name".to_string())
+        );
+        assert_eq!(tables[1].entries[1].value, Value::Integer(-1000));
+        assert_eq!(
+            tables[2].entries[0].value,
+            Value::Array(vec![Value::Boolean(true), Value::Boolean(false)])
+        );
+        assert_eq!(
+            tables[2].entries[1],
+            Entry {
+                key: "sources".to_string(),
+                value: Value::Array(vec![
+                    Value::String("src".to_string()),
+                    Value::String("lib".to_string())
+                ]),
+                line: 8
+            }
+        );
+    }
+
+    #[test]
+    fn reports_errors_with_their_line() {
+        assert_eq!(
+            error("a = 1\na = 2"),
+            "line 2: the key `a` is defined twice"
+        );
+        assert_eq!(
+            error("[a]\n[b]\n[a]"),
+            "line 3: the table `a` is defined twice"
+        );
+        assert_eq!(
+            error("\nname = hello"),
+            "line 2: expected a value, found `hello`; strings need quotes"
+        );
+        assert_eq!(error("name = \"open\n"), "line 1: unterminated string");
+        assert_eq!(error("x = [1, 2\ny = 3"), "line 2: expected `]`, found `y`");
+        assert_eq!(
+            error("x = 1 2"),
+            "line 1: expected the end of the line, found `2`"
+        );
+        assert_eq!(error("x = 1.5"), "line 1: `1.5` is not a valid integer");
+    }
+}
@@ -1,45 +1,253 @@
-use crate::ast::{Expression, Item, Program, Statement};
+use crate::ast::{BinaryOperator, BoxBuilder, UnaryOperator};
 use crate::lexer::Lexer;
 use crate::token::{Token, TokenKind};
 use crate::traits::RangeExt;
-use std::iter::Peekable;
+use std::fmt;
 use std::range::Range;
 
+#[derive(Debug)]
 pub enum ParseError {
-    UndefinedFailure,
     UnexpectedToken(Token, Range<usize>, Option<ExpectedTokenKind>),
     UnexpectedEof(ExpectedTokenKind),
+    /// A block whose `}` is missing. Carries the span of the opening `{` and
+    /// the position where the closing brace was expected.
+    UnterminatedBlock(Range<usize>, Range<usize>),
+    /// A literal that does not denote a valid value, together with its source
+    /// text.
+    InvalidLiteral(String, Range<usize>),
+    InvalidAssignmentTarget(Range<usize>),
+    /// An integer literal, together with its source text, that does not fit
+    /// into an `i64`.
+    IntegerOverflow(String, Range<usize>),
+    /// A character that does not start any token.
+    UnexpectedCharacter(char, Range<usize>),
+    /// A block comment running up to the end of the input.
+    UnterminatedComment(Range<usize>),
 }
 
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedToken(token, _, Some(expected)) => {
+                write!(f, "expected {expected}, found {token}")
+            }
+            ParseError::UnexpectedToken(token, _, None) => write!(f, "unexpected {token}"),
+            ParseError::UnexpectedEof(expected) => {
+                write!(f, "expected {expected}, found end of input")
+            }
+            ParseError::UnterminatedBlock(_, _) => write!(f, "unterminated block"),
+            ParseError::InvalidLiteral(literal, _) => write!(f, "invalid literal `{literal}`"),
+            ParseError::IntegerOverflow(literal, _) => {
+                write!(f, "integer literal `{literal}` is out of range")
+            }
+            ParseError::InvalidAssignmentTarget(_) => write!(f, "invalid assignment target"),
+            ParseError::UnexpectedCharacter(c, _) => {
+                write!(f, "unexpected character `{}`", c.escape_debug())
+            }
+            ParseError::UnterminatedComment(_) => write!(f, "unterminated block comment"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 #[derive(Debug, Clone)]
-pub(crate) enum ExpectedTokenKind {
+pub enum ExpectedTokenKind {
     This(TokenKind),
     AnyOf(Vec<TokenKind>),
 }
 
-pub(crate) struct Parser<'t> {
-    input: Peekable<Lexer<'t>>,
+impl fmt::Display for ExpectedTokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpectedTokenKind::This(kind) => write!(f, "{kind}"),
+            ExpectedTokenKind::AnyOf(kinds) => {
+                let (last, rest) = kinds
+                    .split_last()
+                    .expect("`AnyOf` always lists at least one token kind");
+                if rest.is_empty() {
+                    return write!(f, "{last}");
+                }
+
+                write!(f, "one of ")?;
+                for kind in rest {
+                    write!(f, "{kind}, ")?;
+                }
+                write!(f, "or {last}")
+            }
+        }
+    }
+}
+
+/// Creates the nodes of the tree the parser recognizes. The parser decides
+/// what to build and in which order, the builder how nodes are represented.
+pub trait TreeBuilder {
+    type Program;
+    type Item;
+    type Statement;
+    type Expression;
+
+    fn statement_span(&self, statement: &Self::Statement) -> Range<usize>;
+    fn expression_span(&self, expression: &Self::Expression) -> Range<usize>;
+
+    fn program(&mut self, items: Vec<Self::Item>) -> Self::Program;
+    fn function(
+        &mut self,
+        name: String,
+        arguments: Vec<String>,
+        body: Self::Statement,
+        span: Range<usize>,
+    ) -> Self::Item;
+
+    fn block(&mut self, body: Vec<Self::Statement>, span: Range<usize>) -> Self::Statement;
+    fn binding(
+        &mut self,
+        bind_to: String,
+        value: Self::Expression,
+        span: Range<usize>,
+    ) -> Self::Statement;
+    fn expression_statement(
+        &mut self,
+        expression: Self::Expression,
+        span: Range<usize>,
+    ) -> Self::Statement;
+    fn return_statement(&mut self, value: Self::Expression, span: Range<usize>) -> Self::Statement;
+    fn branch(
+        &mut self,
+        condition: Self::Expression,
+        then: Self::Statement,
+        otherwise: Option<Self::Statement>,
+        span: Range<usize>,
+    ) -> Self::Statement;
+    fn while_loop(
+        &mut self,
+        condition: Self::Expression,
+        body: Self::Statement,
+        span: Range<usize>,
+    ) -> Self::Statement;
+    fn error_statement(&mut self, span: Range<usize>) -> Self::Statement;
+
+    fn integer(&mut self, value: i64, span: Range<usize>) -> Self::Expression;
+    fn boolean(&mut self, value: bool, span: Range<usize>) -> Self::Expression;
+    fn reference(&mut self, name: String, span: Range<usize>) -> Self::Expression;
+    fn unary(
+        &mut self,
+        operator: UnaryOperator,
+        operand: Self::Expression,
+        span: Range<usize>,
+    ) -> Self::Expression;
+    fn binary(
+        &mut self,
+        operator: BinaryOperator,
+        lhs: Self::Expression,
+        rhs: Self::Expression,
+        span: Range<usize>,
+    ) -> Self::Expression;
+    /// Builds `target = value`, or returns `None` if `target` is not a plain
+    /// reference.
+    fn assignment(
+        &mut self,
+        target: Self::Expression,
+        value: Self::Expression,
+        span: Range<usize>,
+    ) -> Option<Self::Expression>;
+    fn call(
+        &mut self,
+        name: String,
+        arguments: Vec<Self::Expression>,
+        span: Range<usize>,
+    ) -> Self::Expression;
+    fn error_expression(&mut self, span: Range<usize>) -> Self::Expression;
+}
+
+/// A piece of interactive input, which mixes functions and statements.
+#[derive(Debug)]
+pub enum Entry<I, S> {
+    Item(I),
+    Statement(S),
+}
+
+/// The entries `Parser::parse_entries` builds with `B`.
+pub type Entries<B> = Vec<Entry<<B as TreeBuilder>::Item, <B as TreeBuilder>::Statement>>;
+
+pub struct Parser<'t, B = BoxBuilder> {
+    input: Lexer<'t>,
+    peeked: Option<(Token, Range<usize>)>,
+    previous: Range<usize>,
+    errors: Vec<ParseError>,
+    builder: B,
 }
 
 impl<'t> Parser<'t> {
+    /// Creates a parser that builds an `ast::Program`.
     pub fn new(input: Lexer<'t>) -> Self {
+        Parser::with_builder(input, BoxBuilder::default())
+    }
+}
+
+impl<'t, B: TreeBuilder> Parser<'t, B> {
+    pub fn with_builder(input: Lexer<'t>, builder: B) -> Self {
         Self {
-            input: input.peekable(),
+            input,
+            peeked: None,
+            previous: Range::from(0..0),
+            errors: Vec::new(),
+            builder,
         }
     }
 
-    pub fn parse(&mut self) -> Result<Program, ParseError> {
+    /// Parses the whole input, recovering from errors where possible. The
+    /// returned program contains `Error` nodes wherever a statement or
+    /// expression could not be parsed, and items that could not be parsed at
+    /// all are left out.
+    pub fn parse(&mut self) -> (B::Program, Vec<ParseError>) {
         let mut items = Vec::new();
 
         while !self.is_eof() {
-            items.push(self.expect_item()?);
+            match self.expect_item() {
+                Ok(item) => items.push(item),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.skip_to_item_boundary();
+                }
+            }
         }
 
-        Ok(Program::new(items))
+        let program = self.builder.program(items);
+
+        (program, std::mem::take(&mut self.errors))
     }
 
-    fn expect_item(&mut self) -> Result<Item, ParseError> {
-        match self.input.peek() {
+    /// Parses input that may contain statements outside of any function, the
+    /// way it is typed into the REPL. Recovers from errors like `parse`.
+    pub fn parse_entries(&mut self) -> (Entries<B>, Vec<ParseError>) {
+        let mut entries = Vec::new();
+
+        while let Some((token, span)) = self.peek() {
+            match token {
+                Token::Fn => match self.expect_function() {
+                    Ok(item) => entries.push(Entry::Item(item)),
+                    Err(error) => {
+                        self.errors.push(error);
+                        self.skip_to_item_boundary();
+                    }
+                },
+                // statement recovery stops in front of a `}`, so a stray one
+                // has to be skipped here
+                Token::RBrace => {
+                    let error = ParseError::UnexpectedToken(token.clone(), *span, None);
+                    self.errors.push(error);
+                    self.next();
+                }
+                _ => entries.push(Entry::Statement(self.recover_statement())),
+            }
+        }
+
+        (entries, std::mem::take(&mut self.errors))
+    }
+
+    fn expect_item(&mut self) -> Result<B::Item, ParseError> {
+        match self.peek() {
             Some((Token::Fn, _)) => self.expect_function(),
             Some((token, span)) => Err(ParseError::UnexpectedToken(token.clone(), *span, None)),
             None => Err(ParseError::UnexpectedEof(ExpectedTokenKind::This(
@@ -48,28 +256,35 @@ impl<'t> Parser<'t> {
         }
     }
 
-    fn expect_function(&mut self) -> Result<Item, ParseError> {
-        let _ = self.expect_token(TokenKind::Fn);
-        let name = self.expect_identifier()?;
-        let _ = self.expect_token(TokenKind::LParen);
-        let arguments = self.expect_arguments();
-        let _ = self.expect_token(TokenKind::RParen);
+    fn expect_function(&mut self) -> Result<B::Item, ParseError> {
+        let (_, start) = self.expect_token(TokenKind::Fn)?;
+        let (name, _) = self.expect_identifier()?;
+        self.expect_token(TokenKind::LParen)?;
+        let arguments = self.expect_arguments()?;
+        self.expect_token(TokenKind::RParen)?;
         let body = self.expect_block()?;
-        todo!()
+
+        let end = self.builder.statement_span(&body);
+
+        Ok(self
+            .builder
+            .function(name, arguments, body, start.merge(&end)))
     }
 
     fn expect_arguments(&mut self) -> Result<Vec<String>, ParseError> {
         let mut arguments = Vec::new();
 
-        if let Some((Token::RParen, _)) = self.input.peek() {
+        if let Some((Token::RParen, _)) = self.peek() {
             return Ok(arguments);
         }
 
         let (arg, _) = self.expect_identifier()?;
         arguments.push(arg);
 
-        while let Some((Token::Comma, _)) = self.input.peek() {
-            self.input.next();
+        while self
+            .next_if(|token| token.kind() == TokenKind::Comma)
+            .is_some()
+        {
             let (arg, _) = self.expect_identifier()?;
             arguments.push(arg);
         }
@@ -77,62 +292,79 @@ impl<'t> Parser<'t> {
         Ok(arguments)
     }
 
-    fn expect_block(&mut self) -> Result<Statement, ParseError> {
+    fn expect_block(&mut self) -> Result<B::Statement, ParseError> {
         let (_, start) = self.expect_token(TokenKind::LBrace)?;
-
-        if let Some((Token::RBrace, end)) = self.input.peek() {
-            return Ok(Statement::Block {
-                body: Vec::new(),
-                span: start.merge(&end),
-            });
-        }
-
         let mut statements = Vec::new();
 
-        while let Some((token, _)) = self.input.peek()
-            && token.kind() != TokenKind::RBrace
+        // a `fn` cannot appear inside a block, so it most likely means that
+        // the closing brace is missing
+        while let Some((token, _)) = self.peek()
+            && !matches!(token, Token::RBrace | Token::Fn)
         {
-            let statement = self.expect_statement()?;
-            statements.push(statement);
+            statements.push(self.recover_statement());
         }
 
-        // eat unconsumed Token::RBrace
-        let end = match self.input.next() {
-            Some((Token::RBrace, span)) => span,
-            Some((token, span)) => {
-                return Err(ParseError::UnexpectedToken(
-                    token,
-                    span,
-                    Some(ExpectedTokenKind::This(TokenKind::RBrace)),
-                ));
-            }
+        let end = match self.next_if(|token| token.kind() == TokenKind::RBrace) {
+            Some((_, end)) => end,
             None => {
-                return Err(ParseError::UnexpectedEof(ExpectedTokenKind::This(
-                    TokenKind::RBrace,
-                )));
+                let expected_at = self.peek_span();
+                self.errors
+                    .push(ParseError::UnterminatedBlock(start, expected_at));
+                self.previous
             }
         };
 
-        Ok(Statement::Block {
-            body: statements,
-            span: start.merge(&end),
-        })
+        Ok(self.builder.block(statements, start.merge(&end)))
+    }
+
+    fn recover_statement(&mut self) -> B::Statement {
+        let start = self.peek_span();
+
+        match self.expect_statement() {
+            Ok(statement) => statement,
+            Err(error) => {
+                self.errors.push(error);
+                self.skip_to_statement_boundary();
+
+                let span = self.span_since(start);
+                self.builder.error_statement(span)
+            }
+        }
     }
 
-    fn expect_statement(&mut self) -> Result<Statement, ParseError> {
+    fn expect_statement(&mut self) -> Result<B::Statement, ParseError> {
         let expected_token_kinds = vec![
             TokenKind::LBrace,
             TokenKind::Let,
             TokenKind::Return,
             TokenKind::If,
             TokenKind::While,
+            TokenKind::LParen,
+            TokenKind::Int,
+            TokenKind::True,
+            TokenKind::False,
+            TokenKind::Identifier,
+            TokenKind::Bang,
+            TokenKind::Minus,
         ];
-        match self.input.peek() {
+        match self.peek() {
             Some((Token::LBrace, _)) => self.expect_block(),
             Some((Token::Let, _)) => self.expect_binding(),
             Some((Token::Return, _)) => self.expect_return(),
             Some((Token::If, _)) => self.expect_branching(),
             Some((Token::While, _)) => self.expect_while(),
+            Some((
+                Token::LParen
+                | Token::Int(_)
+                | Token::IntOverflow(_)
+                | Token::InvalidInt(_)
+                | Token::True
+                | Token::False
+                | Token::Identifier(_)
+                | Token::Bang
+                | Token::Minus,
+                _,
+            )) => self.expect_expression_statement(),
             Some((token, span)) => Err(ParseError::UnexpectedToken(
                 token.clone(),
                 *span,
@@ -144,127 +376,1002 @@ impl<'t> Parser<'t> {
         }
     }
 
-    fn expect_binding(&mut self) -> Result<Statement, ParseError> {
+    fn expect_binding(&mut self) -> Result<B::Statement, ParseError> {
         let (_, start) = self.expect_token(TokenKind::Let)?;
         let (name, _) = self.expect_identifier()?;
-        let _ = self.expect_token(TokenKind::Equal)?;
-        let value = self.expect_expression(0)?;
+        self.expect_token(TokenKind::Equal)?;
+        let value = self.recover_expression();
         let (_, end) = self.expect_token(TokenKind::Semicolon)?;
 
-        Ok(Statement::Binding {
-            bind_to: name,
-            value,
-            span: start.merge(&end),
-        })
+        Ok(self.builder.binding(name, value, start.merge(&end)))
     }
 
-    fn expect_return(&mut self) -> Result<Statement, ParseError> {
+    fn expect_expression_statement(&mut self) -> Result<B::Statement, ParseError> {
+        let start = self.peek_span();
+        let expression = self.recover_expression();
+        let (_, end) = self.expect_token(TokenKind::Semicolon)?;
+        let span = start.merge(&end);
+
+        Ok(self.builder.expression_statement(expression, span))
+    }
+
+    fn expect_return(&mut self) -> Result<B::Statement, ParseError> {
         let (_, start) = self.expect_token(TokenKind::Return)?;
-        let value = self.expect_expression(0)?;
+        let value = self.recover_expression();
         let (_, end) = self.expect_token(TokenKind::Semicolon)?;
 
-        Ok(Statement::Return {
-            value,
-            span: start.merge(&end),
-        })
+        Ok(self.builder.return_statement(value, start.merge(&end)))
     }
 
-    fn expect_branching(&mut self) -> Result<Statement, ParseError> {
+    fn expect_branching(&mut self) -> Result<B::Statement, ParseError> {
         let (_, start) = self.expect_token(TokenKind::If)?;
-        let condition = self.expect_expression(0)?;
+        let condition = self.recover_expression();
         let then = self.expect_block()?;
 
-        if let Some((Token::Else, _)) = self.input.peek() {
-            self.input.next();
+        if self
+            .next_if(|token| token.kind() == TokenKind::Else)
+            .is_some()
+        {
             let otherwise = self.expect_block()?;
 
-            let end = otherwise.span();
+            let end = self.builder.statement_span(&otherwise);
 
-            return Ok(Statement::Branch {
-                condition,
-                then: Box::new(then),
-                otherwise: Some(Box::new(otherwise)),
-                span: start.merge(&end),
-            });
+            return Ok(self
+                .builder
+                .branch(condition, then, Some(otherwise), start.merge(&end)));
         }
 
-        let end = then.span();
+        let end = self.builder.statement_span(&then);
 
-        Ok(Statement::Branch {
-            condition,
-            then: Box::new(then),
-            otherwise: None,
-            span: start.merge(&end),
-        })
+        Ok(self
+            .builder
+            .branch(condition, then, None, start.merge(&end)))
     }
 
-    fn expect_while(&mut self) -> Result<Statement, ParseError> {
+    fn expect_while(&mut self) -> Result<B::Statement, ParseError> {
         let (_, start) = self.expect_token(TokenKind::While)?;
-        let condition = self.expect_expression(0)?;
+        let condition = self.recover_expression();
         let body = self.expect_block()?;
 
-        let end = body.span();
+        let end = self.builder.statement_span(&body);
+
+        Ok(self.builder.while_loop(condition, body, start.merge(&end)))
+    }
+
+    fn recover_expression(&mut self) -> B::Expression {
+        let start = self.peek_span();
+
+        match self.expect_expression(0) {
+            Ok(expression) => expression,
+            Err(error) => {
+                self.errors.push(error);
+                self.skip_to_expression_boundary();
 
-        Ok(Statement::While {
-            condition,
-            body: Box::new(body),
-            span: start.merge(&end),
-        })
+                let span = self.span_since(start);
+                self.builder.error_expression(span)
+            }
+        }
     }
 
-    fn expect_expression(&mut self, rbp: u8) -> Result<Expression, ParseError> {
+    fn expect_expression(&mut self, rbp: u8) -> Result<B::Expression, ParseError> {
         let mut left = self.expect_nud()?;
-        while let Some((token, _)) = self.input.peek()
-            && Parser::binding_power(token.kind()) > rbp
+        while let Some((token, _)) = self.peek()
+            && Self::binding_power(token.kind()) > rbp
         {
             left = self.expect_lud(left)?;
         }
         Ok(left)
     }
 
-    fn expect_nud(&mut self) -> Result<Expression, ParseError> {
-        todo!()
+    fn expect_nud(&mut self) -> Result<B::Expression, ParseError> {
+        let expected_token_kinds = vec![
+            TokenKind::LParen,
+            TokenKind::Int,
+            TokenKind::True,
+            TokenKind::False,
+            TokenKind::Identifier,
+            TokenKind::Bang,
+            TokenKind::Minus,
+        ];
+        match self.peek() {
+            Some((
+                Token::Int(_)
+                | Token::IntOverflow(_)
+                | Token::InvalidInt(_)
+                | Token::True
+                | Token::False
+                | Token::Identifier(_)
+                | Token::LParen
+                | Token::Bang
+                | Token::Minus,
+                _,
+            )) => {}
+            Some((token, span)) => {
+                return Err(ParseError::UnexpectedToken(
+                    token.clone(),
+                    *span,
+                    Some(ExpectedTokenKind::AnyOf(expected_token_kinds)),
+                ));
+            }
+            None => {
+                return Err(ParseError::UnexpectedEof(ExpectedTokenKind::AnyOf(
+                    expected_token_kinds,
+                )));
+            }
+        }
+
+        match self.next() {
+            Some((Token::Int(value), span)) => match i64::try_from(value) {
+                Ok(value) => Ok(self.builder.integer(value, span)),
+                Err(_) => Ok(self
+                    .invalid_literal(ParseError::IntegerOverflow(value.to_string(), span), span)),
+            },
+            Some((Token::IntOverflow(literal), span)) => {
+                Ok(self.invalid_literal(ParseError::IntegerOverflow(literal, span), span))
+            }
+            Some((Token::InvalidInt(literal), span)) => {
+                Ok(self.invalid_literal(ParseError::InvalidLiteral(literal, span), span))
+            }
+            Some((Token::True, span)) => Ok(self.builder.boolean(true, span)),
+            Some((Token::False, span)) => Ok(self.builder.boolean(false, span)),
+            Some((Token::Identifier(name), span)) => {
+                if let Some((Token::LParen, _)) = self.peek() {
+                    return self.expect_call(name, span);
+                }
+
+                Ok(self.builder.reference(name, span))
+            }
+            Some((Token::LParen, _)) => {
+                let expression = self.expect_expression(0)?;
+                self.expect_token(TokenKind::RParen)?;
+
+                Ok(expression)
+            }
+            Some((Token::Bang, start)) => {
+                let operand = self.expect_expression(Self::PREFIX_BINDING_POWER)?;
+                let span = start.merge(&self.builder.expression_span(&operand));
+
+                Ok(self.builder.unary(UnaryOperator::Not, operand, span))
+            }
+            Some((Token::Minus, start)) => {
+                // negative literals are folded right away, since the magnitude
+                // of `i64::MIN` does not fit into an `i64` by itself
+                if let Some((Token::Int(magnitude), end)) = self.peek()
+                    && let Some(value) = 0i64.checked_sub_unsigned(*magnitude)
+                {
+                    let span = start.merge(end);
+                    self.next();
+
+                    return Ok(self.builder.integer(value, span));
+                }
+
+                let operand = self.expect_expression(Self::PREFIX_BINDING_POWER)?;
+                let span = start.merge(&self.builder.expression_span(&operand));
+
+                Ok(self.builder.unary(UnaryOperator::Negate, operand, span))
+            }
+            _ => unreachable!("the token was checked above"),
+        }
+    }
+
+    /// Records `error` and stands in for the literal, so that a bad literal
+    /// does not derail the rest of the expression.
+    fn invalid_literal(&mut self, error: ParseError, span: Range<usize>) -> B::Expression {
+        self.errors.push(error);
+
+        self.builder.error_expression(span)
     }
 
-    fn expect_lud(&mut self, left: Expression) -> Result<Expression, ParseError> {
-        todo!()
+    fn expect_lud(&mut self, left: B::Expression) -> Result<B::Expression, ParseError> {
+        let (operator, _) = self
+            .next()
+            .expect("`expect_expression` only calls this after peeking an operator");
+        let binding_power = Self::binding_power(operator.kind());
+
+        // assignment is the only right-associative operator
+        if let Token::Equal = operator {
+            let value = self.expect_expression(binding_power - 1)?;
+
+            let target = self.builder.expression_span(&left);
+            let span = target.merge(&self.builder.expression_span(&value));
+
+            return self
+                .builder
+                .assignment(left, value, span)
+                .ok_or(ParseError::InvalidAssignmentTarget(target));
+        }
+
+        let right = self.expect_expression(binding_power)?;
+        let span = self
+            .builder
+            .expression_span(&left)
+            .merge(&self.builder.expression_span(&right));
+
+        let operator = match operator {
+            Token::Plus => BinaryOperator::Add,
+            Token::Minus => BinaryOperator::Sub,
+            Token::Asterisk => BinaryOperator::Mul,
+            Token::Slash => BinaryOperator::Div,
+            Token::EqualEqual => BinaryOperator::Equal,
+            Token::BangEqual => BinaryOperator::NotEqual,
+            Token::Less => BinaryOperator::Less,
+            Token::LessEqual => BinaryOperator::LessEqual,
+            Token::Greater => BinaryOperator::Greater,
+            Token::GreaterEqual => BinaryOperator::GreaterEqual,
+            Token::AndAnd => BinaryOperator::And,
+            Token::OrOr => BinaryOperator::Or,
+            _ => unreachable!("`binding_power` is zero for every non-infix token"),
+        };
+
+        Ok(self.builder.binary(operator, left, right, span))
+    }
+
+    fn expect_call(
+        &mut self,
+        name: String,
+        start: Range<usize>,
+    ) -> Result<B::Expression, ParseError> {
+        self.expect_token(TokenKind::LParen)?;
+
+        let mut arguments = Vec::new();
+
+        if !matches!(self.peek(), Some((Token::RParen, _))) {
+            arguments.push(self.expect_expression(0)?);
+
+            while self
+                .next_if(|token| token.kind() == TokenKind::Comma)
+                .is_some()
+            {
+                arguments.push(self.expect_expression(0)?);
+            }
+        }
+
+        let (_, end) = self.expect_token(TokenKind::RParen)?;
+
+        Ok(self.builder.call(name, arguments, start.merge(&end)))
     }
 
     fn expect_identifier(&mut self) -> Result<(String, Range<usize>), ParseError> {
-        match self.input.next() {
-            Some((Token::Identifier(identifier), span)) => Ok((identifier, span)),
-            Some((token, span)) => Err(ParseError::UnexpectedToken(
-                token,
-                span,
-                Some(ExpectedTokenKind::This(TokenKind::Identifier)),
-            )),
-            None => Err(ParseError::UnexpectedEof(ExpectedTokenKind::This(
-                TokenKind::Identifier,
-            ))),
+        match self.expect_token(TokenKind::Identifier)? {
+            (Token::Identifier(identifier), span) => Ok((identifier, span)),
+            _ => unreachable!("`expect_token` checked the token kind"),
         }
     }
 
+    /// Consumes the next token if it is of the given kind. Any other token is
+    /// left in place so that error recovery can decide what to skip.
     fn expect_token(&mut self, kind: TokenKind) -> Result<(Token, Range<usize>), ParseError> {
-        match self.input.next() {
-            Some((token, span)) if token.kind() == kind => Ok((token, span)),
+        if let Some(next) = self.next_if(|token| token.kind() == kind) {
+            return Ok(next);
+        }
+
+        match self.peek() {
             Some((token, span)) => Err(ParseError::UnexpectedToken(
-                token,
-                span,
+                token.clone(),
+                *span,
                 Some(ExpectedTokenKind::This(kind)),
             )),
             None => Err(ParseError::UnexpectedEof(ExpectedTokenKind::This(kind))),
         }
     }
 
-    fn is_eof(&mut self) -> bool {
-        if let None = self.input.peek() {
-            true
-        } else {
-            false
+    fn next(&mut self) -> Option<(Token, Range<usize>)> {
+        self.next_if(|_| true)
+    }
+
+    fn next_if(&mut self, f: impl FnOnce(&Token) -> bool) -> Option<(Token, Range<usize>)> {
+        if !f(&self.peek()?.0) {
+            return None;
         }
+
+        self.peeked
+            .take()
+            .inspect(|(_, span)| self.previous = *span)
     }
 
+    /// Peeks at the next token. Error tokens produced by the lexer are
+    /// recorded as errors and never reach the grammar.
+    fn peek(&mut self) -> Option<&(Token, Range<usize>)> {
+        while self.peeked.is_none() {
+            match self.input.next()? {
+                (Token::UnexpectedCharacter(c), span) => {
+                    self.errors.push(ParseError::UnexpectedCharacter(c, span));
+                }
+                (Token::UnterminatedComment, span) => {
+                    self.errors.push(ParseError::UnterminatedComment(span));
+                }
+                token => self.peeked = Some(token),
+            }
+        }
+
+        self.peeked.as_ref()
+    }
+
+    /// Span of the next token, or an empty span behind the last token at the
+    /// end of the input.
+    fn peek_span(&mut self) -> Range<usize> {
+        match self.peek() {
+            Some((_, span)) => *span,
+            None => Range::from(self.previous.end..self.previous.end),
+        }
+    }
+
+    /// Span from the start of `start` up to the end of the last consumed token.
+    fn span_since(&self, start: Range<usize>) -> Range<usize> {
+        Range::from(start.start..self.previous.end.max(start.start))
+    }
+
+    fn skip_to_item_boundary(&mut self) {
+        while self
+            .next_if(|token| token.kind() != TokenKind::Fn)
+            .is_some()
+        {}
+    }
+
+    /// Skips past the next `;` or up to the `}` closing the current block,
+    /// jumping over nested blocks on the way.
+    fn skip_to_statement_boundary(&mut self) {
+        let mut depth = 0usize;
+
+        while let Some((token, _)) = self.peek() {
+            match token {
+                Token::Fn => return,
+                Token::RBrace if depth == 0 => return,
+                Token::Semicolon if depth == 0 => {
+                    self.next();
+                    return;
+                }
+                Token::LBrace => depth += 1,
+                Token::RBrace => depth -= 1,
+                _ => {}
+            }
+
+            self.next();
+        }
+    }
+
+    /// Skips up to, but not including, the next token that may continue the
+    /// enclosing statement.
+    fn skip_to_expression_boundary(&mut self) {
+        while self
+            .next_if(|token| {
+                !matches!(
+                    token,
+                    Token::Semicolon | Token::LBrace | Token::RBrace | Token::Fn
+                )
+            })
+            .is_some()
+        {}
+    }
+
+    fn is_eof(&mut self) -> bool {
+        self.peek().is_none()
+    }
+
+    const PREFIX_BINDING_POWER: u8 = 8;
+
+    /// Binding powers of the infix operators, from loosest to tightest:
+    /// `=` (right-associative), `||`, `&&`, `==` `!=`, `<` `<=` `>` `>=`,
+    /// `+` `-`, `*` `/`. Prefix `!` binds tighter than all of them.
     fn binding_power(kind: TokenKind) -> u8 {
-        todo!()
+        match kind {
+            TokenKind::Equal => 1,
+            TokenKind::OrOr => 2,
+            TokenKind::AndAnd => 3,
+            TokenKind::EqualEqual | TokenKind::BangEqual => 4,
+            TokenKind::Less
+            | TokenKind::LessEqual
+            | TokenKind::Greater
+            | TokenKind::GreaterEqual => 5,
+            TokenKind::Plus | TokenKind::Minus => 6,
+            TokenKind::Asterisk | TokenKind::Slash => 7,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{
+        BinaryOperation, Expression, Item, Operation, Program, Statement, UnaryOperation,
+    };
+
+    fn parse_expression(source: &str) -> Result<Expression, ParseError> {
+        let mut parser = Parser::new(Lexer::new(source));
+        let expression = parser.expect_expression(0)?;
+        assert!(parser.is_eof(), "`{source}` was not fully consumed");
+
+        Ok(expression)
+    }
+
+    fn parse_program(source: &str) -> Program {
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+        assert!(errors.is_empty(), "`{source}` failed to parse: {errors:?}");
+
+        program
+    }
+
+    fn function_body(item: &Item) -> &[Statement] {
+        let Item::Function { body, .. } = item;
+        let Statement::Block { body, .. } = body else {
+            panic!("function body should be a block");
+        };
+
+        body
+    }
+
+    fn sexpr(expression: &Expression) -> String {
+        match expression {
+            Expression::Integer { value, .. } => value.to_string(),
+            Expression::Boolean { value, .. } => value.to_string(),
+            Expression::Reference { name, .. } => name.clone(),
+            Expression::Assignment {
+                assign_to, value, ..
+            } => format!("(= {assign_to} {})", sexpr(value)),
+            Expression::Call {
+                name, arguments, ..
+            } => {
+                let arguments = arguments.iter().map(sexpr).collect::<Vec<_>>();
+                format!("(call {name} [{}])", arguments.join(" "))
+            }
+            Expression::Error { .. } => "<error>".to_string(),
+            Expression::Operation {
+                operation: Operation::Unary(UnaryOperation::Not(operand)),
+                ..
+            } => format!("(! {})", sexpr(operand)),
+            Expression::Operation {
+                operation: Operation::Unary(UnaryOperation::Negate(operand)),
+                ..
+            } => format!("(neg {})", sexpr(operand)),
+            Expression::Operation {
+                operation: Operation::Binary(operation),
+                ..
+            } => {
+                let (operator, lhs, rhs) = match operation {
+                    BinaryOperation::Add { lhs, rhs } => ("+", lhs, rhs),
+                    BinaryOperation::Sub { lhs, rhs } => ("-", lhs, rhs),
+                    BinaryOperation::Mul { lhs, rhs } => ("*", lhs, rhs),
+                    BinaryOperation::Div { lhs, rhs } => ("/", lhs, rhs),
+                    BinaryOperation::Equal { lhs, rhs } => ("==", lhs, rhs),
+                    BinaryOperation::NotEqual { lhs, rhs } => ("!=", lhs, rhs),
+                    BinaryOperation::Less { lhs, rhs } => ("<", lhs, rhs),
+                    BinaryOperation::LessEqual { lhs, rhs } => ("<=", lhs, rhs),
+                    BinaryOperation::Greater { lhs, rhs } => (">", lhs, rhs),
+                    BinaryOperation::GreaterEqual { lhs, rhs } => (">=", lhs, rhs),
+                    BinaryOperation::And { lhs, rhs } => ("&&", lhs, rhs),
+                    BinaryOperation::Or { lhs, rhs } => ("||", lhs, rhs),
+                };
+                format!("({operator} {} {})", sexpr(lhs), sexpr(rhs))
+            }
+        }
+    }
+
+    #[test]
+    fn parses_example_program() {
+        let source = include_str!("../examples/example1.inv");
+        let program = parse_program(source);
+
+        let [
+            Item::Function {
+                name,
+                arguments,
+                body,
+                span,
+                ..
+            },
+        ] = program.items()
+        else {
+            panic!("expected exactly one function");
+        };
+        assert_eq!(name, "main");
+        assert!(arguments.is_empty());
+        assert_eq!(span, &Range::from(0..source.trim_end().len()));
+
+        let Statement::Block { body, .. } = body else {
+            panic!("function body should be a block");
+        };
+        assert!(matches!(
+            body.as_slice(),
+            [Statement::Binding { .. }, Statement::Return { .. }]
+        ));
+    }
+
+    #[test]
+    fn parses_function_signatures() {
+        let cases = [
+            ("fn f() {}", vec![]),
+            ("fn f(a) {}", vec!["a"]),
+            ("fn f(a, b, c) { return a; }", vec!["a", "b", "c"]),
+        ];
+
+        for (source, expected) in cases {
+            let program = parse_program(source);
+            let [Item::Function { arguments, .. }] = program.items() else {
+                panic!("expected exactly one function in `{source}`");
+            };
+            assert_eq!(arguments, &expected[..], "while parsing `{source}`");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_signatures() {
+        let cases = [
+            "fn () {}",
+            "fn f {}",
+            "fn f(a {}",
+            "fn f(a,) {}",
+            "fn f(a b) {}",
+            "fn f(1) {}",
+            "fn f()",
+            "fn f() { return 1; ",
+        ];
+
+        for source in cases {
+            let (_, errors) = Parser::new(Lexer::new(source)).parse();
+            assert!(!errors.is_empty(), "`{source}` should not parse");
+        }
+    }
+
+    #[test]
+    fn parses_expression_statements() {
+        let source = "fn main() { let x = 0; while x < 10 { x = x + 1; print(x); } (x); }";
+        let program = parse_program(source);
+
+        let [Item::Function { body, .. }] = program.items() else {
+            panic!("expected exactly one function");
+        };
+        let Statement::Block { body, .. } = body else {
+            panic!("function body should be a block");
+        };
+        let [
+            _,
+            Statement::While { body, .. },
+            Statement::Expression {
+                expression, span, ..
+            },
+        ] = body.as_slice()
+        else {
+            panic!("expected a binding, a loop and an expression statement");
+        };
+        assert_eq!(sexpr(expression), "x");
+        assert_eq!(&source[*span], "(x);");
+
+        let Statement::Block { body, .. } = body.as_ref() else {
+            panic!("loop body should be a block");
+        };
+        let expressions = body
+            .iter()
+            .map(|statement| match statement {
+                Statement::Expression {
+                    expression, span, ..
+                } => (sexpr(expression), &source[*span]),
+                _ => panic!("expected only expression statements in the loop body"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            expressions,
+            [
+                ("(= x (+ x 1))".to_string(), "x = x + 1;"),
+                ("(call print [x])".to_string(), "print(x);"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unterminated_expression_statements() {
+        let cases = [
+            "fn main() { x = 1 }",
+            "fn main() { print(x) }",
+            "fn main() { x; y }",
+            "fn main() { ; }",
+        ];
+
+        for source in cases {
+            let (_, errors) = Parser::new(Lexer::new(source)).parse();
+            assert!(!errors.is_empty(), "`{source}` should not parse");
+        }
+    }
+
+    #[test]
+    fn collects_every_error() {
+        let source = "fn main() { let = 1; return 1 +; x = ; }";
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        let [function] = program.items() else {
+            panic!("expected exactly one function");
+        };
+        let [
+            Statement::Error { span, .. },
+            Statement::Return { value, .. },
+            Statement::Expression { expression, .. },
+        ] = function_body(function)
+        else {
+            panic!("expected an error statement, a return and an expression statement");
+        };
+        assert_eq!(&source[*span], "let = 1;");
+        assert!(matches!(value, Expression::Error { .. }));
+        assert_eq!(&source[value.span()], "1 +");
+        assert!(matches!(expression, Expression::Error { .. }));
+        assert_eq!(&source[expression.span()], "x =");
+    }
+
+    #[test]
+    fn recovers_inside_nested_blocks() {
+        let source = "fn main() { while x { ) y; } if 1 +  { z; } return 0; }";
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        let [function] = program.items() else {
+            panic!("expected exactly one function");
+        };
+        let [
+            Statement::While { body, .. },
+            Statement::Branch {
+                condition, then, ..
+            },
+            Statement::Return { .. },
+        ] = function_body(function)
+        else {
+            panic!("expected a loop, a branch and a return");
+        };
+
+        let Statement::Block { body, .. } = body.as_ref() else {
+            panic!("loop body should be a block");
+        };
+        assert!(
+            matches!(body.as_slice(), [Statement::Error { span, .. }] if &source[*span] == ") y;")
+        );
+        assert_eq!(sexpr(condition), "<error>");
+        assert!(matches!(
+            then.as_ref(),
+            Statement::Block { body, .. } if matches!(body.as_slice(), [Statement::Expression { .. }])
+        ));
+    }
+
+    #[test]
+    fn recovers_at_the_next_item() {
+        let source = "let x = 1; fn f(a b) { return a; } fn g() { return 1; } fn h() { return 2;";
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(matches!(
+            errors[0],
+            ParseError::UnexpectedToken(Token::Let, _, None)
+        ));
+        assert!(matches!(
+            errors[1],
+            ParseError::UnexpectedToken(
+                Token::Identifier(_),
+                _,
+                Some(ExpectedTokenKind::This(TokenKind::RParen))
+            )
+        ));
+        assert!(matches!(
+            errors[2],
+            ParseError::UnterminatedBlock(opened, _) if &source[opened] == "{"
+        ));
+
+        let names = program
+            .items()
+            .iter()
+            .map(|Item::Function { name, .. }| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["g", "h"]);
+    }
+
+    #[test]
+    fn closes_unterminated_blocks_before_the_next_item() {
+        let source = "fn f() { return 1; fn g() {}";
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+
+        assert!(matches!(
+            errors.as_slice(),
+            [ParseError::UnterminatedBlock(opened, expected_at)]
+                if *opened == Range::from(7..8) && &source[*expected_at] == "fn"
+        ));
+        let [Item::Function { span, .. }, _] = program.items() else {
+            panic!("expected two functions");
+        };
+        assert_eq!(&source[*span], "fn f() { return 1;");
+    }
+
+    #[test]
+    fn describes_errors() {
+        let cases = [
+            ("fn main() { let x = 1 }", "expected `;`, found `}`"),
+            (
+                "fn main() { let 1 = x; }",
+                "expected identifier, found integer literal `1`",
+            ),
+            (
+                "fn main() { return",
+                "expected one of `(`, integer literal, `true`, `false`, identifier, `!`, or `-`, found end of input",
+            ),
+            (
+                "fn main() { foo = ; }",
+                "expected one of `(`, integer literal, `true`, `false`, identifier, `!`, or `-`, found `;`",
+            ),
+            ("fn main() {", "unterminated block"),
+            ("fn main() { 1 = 2; }", "invalid assignment target"),
+            ("main", "unexpected identifier `main`"),
+        ];
+
+        for (source, expected) in cases {
+            let (_, errors) = Parser::new(Lexer::new(source)).parse();
+            assert_eq!(
+                errors.first().map(ToString::to_string).as_deref(),
+                Some(expected),
+                "while parsing `{source}`"
+            );
+        }
+    }
+
+    #[test]
+    fn reports_unexpected_characters() {
+        let source = "fn main() { let x = 1 @ 2; return x & 1; }";
+        let (_, errors) = Parser::new(Lexer::new(source)).parse();
+
+        let [
+            ParseError::UnexpectedCharacter('@', at),
+            ParseError::UnexpectedToken(Token::Int(2), _, _),
+            ParseError::UnexpectedCharacter('&', ampersand),
+            ParseError::UnexpectedToken(Token::Int(1), _, _),
+        ] = errors.as_slice()
+        else {
+            panic!("unexpected errors: {errors:?}");
+        };
+        assert_eq!(&source[*at], "@");
+        assert_eq!(&source[*ampersand], "&");
+        assert_eq!(errors[0].to_string(), "unexpected character `@`");
+    }
+
+    #[test]
+    fn reports_unterminated_comments() {
+        let source = "fn main() { return 1; } /* fn f() {}";
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+
+        assert_eq!(program.items().len(), 1);
+        let [ParseError::UnterminatedComment(span)] = errors.as_slice() else {
+            panic!("unexpected errors: {errors:?}");
+        };
+        assert_eq!(&source[*span], "/* fn f() {}");
+    }
+
+    #[test]
+    fn skips_unexpected_characters() {
+        let source = "fn main() { let % x = 1; }";
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+
+        assert!(matches!(
+            errors.as_slice(),
+            [ParseError::UnexpectedCharacter('%', _)]
+        ));
+        let [function] = program.items() else {
+            panic!("expected exactly one function");
+        };
+        assert!(matches!(
+            function_body(function),
+            [Statement::Binding { .. }]
+        ));
+    }
+
+    #[test]
+    fn parses_negation() {
+        let cases = [
+            ("-1", "-1"),
+            ("- 1", "-1"),
+            ("-0", "0"),
+            ("-x", "(neg x)"),
+            ("--1", "(neg -1)"),
+            ("-(1)", "(neg 1)"),
+            ("-2 * 3", "(* -2 3)"),
+            ("-x * 3", "(* (neg x) 3)"),
+            ("a - -1", "(- a -1)"),
+            ("a -1", "(- a 1)"),
+            ("-f(x)", "(neg (call f [x]))"),
+            ("!-x", "(! (neg x))"),
+            ("9223372036854775807", "9223372036854775807"),
+            ("-9223372036854775808", "-9223372036854775808"),
+        ];
+
+        for (source, expected) in cases {
+            let expression = parse_expression(source).unwrap();
+            assert_eq!(sexpr(&expression), expected, "while parsing `{source}`");
+        }
+    }
+
+    #[test]
+    fn reports_integer_overflow() {
+        let cases = [
+            ("9223372036854775808", "9223372036854775808", 0..19),
+            ("-9223372036854775809", "9223372036854775809", 1..20),
+            ("-(9223372036854775808)", "9223372036854775808", 2..21),
+            ("1 + 18446744073709551616", "18446744073709551616", 4..24),
+        ];
+
+        for (source, literal, span) in cases {
+            let mut parser = Parser::new(Lexer::new(source));
+            let expression = parser.expect_expression(0).unwrap();
+
+            let [ParseError::IntegerOverflow(reported, reported_span)] = parser.errors.as_slice()
+            else {
+                panic!("expected an overflow while parsing `{source}`");
+            };
+            assert_eq!(reported, literal, "while parsing `{source}`");
+            assert_eq!(
+                *reported_span,
+                Range::from(span),
+                "while parsing `{source}`"
+            );
+            assert!(
+                sexpr(&expression).contains("<error>"),
+                "while parsing `{source}`"
+            );
+        }
+    }
+
+    #[test]
+    fn reports_malformed_literals() {
+        let source = "fn main() { let x = 0xZZ + 1; return 0b2; }";
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+
+        let messages = errors.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            ["invalid literal `0xZZ`", "invalid literal `0b2`"]
+        );
+
+        let [function] = program.items() else {
+            panic!("expected exactly one function");
+        };
+        let [
+            Statement::Binding { value, .. },
+            Statement::Return {
+                value: returned, ..
+            },
+        ] = function_body(function)
+        else {
+            panic!("expected a binding and a return");
+        };
+        assert_eq!(sexpr(value), "(+ <error> 1)");
+        assert_eq!(sexpr(returned), "<error>");
+    }
+
+    #[test]
+    fn parses_atoms() {
+        let cases = [
+            ("42", "42"),
+            ("true", "true"),
+            ("false", "false"),
+            ("x", "x"),
+            ("(x)", "x"),
+            ("((1))", "1"),
+            ("f()", "(call f [])"),
+            ("f(1, x, g(2))", "(call f [1 x (call g [2])])"),
+        ];
+
+        for (source, expected) in cases {
+            let expression = parse_expression(source).unwrap();
+            assert_eq!(sexpr(&expression), expected, "while parsing `{source}`");
+        }
+    }
+
+    #[test]
+    fn binary_operators_are_left_associative() {
+        let operators = [
+            "+", "-", "*", "/", "==", "!=", "<", "<=", ">", ">=", "&&", "||",
+        ];
+
+        for operator in operators {
+            let source = format!("a {operator} b {operator} c");
+            let expected = format!("({operator} ({operator} a b) c)");
+            let expression = parse_expression(&source).unwrap();
+            assert_eq!(sexpr(&expression), expected, "while parsing `{source}`");
+        }
+    }
+
+    #[test]
+    fn operators_respect_precedence() {
+        // each row lists operators of one precedence level, loosest first
+        let levels: [&[&str]; 6] = [
+            &["||"],
+            &["&&"],
+            &["==", "!="],
+            &["<", "<=", ">", ">="],
+            &["+", "-"],
+            &["*", "/"],
+        ];
+
+        for (index, looser) in levels.iter().enumerate() {
+            for tighter in levels.iter().skip(index + 1) {
+                for loose in looser.iter() {
+                    for tight in tighter.iter() {
+                        let cases = [
+                            (
+                                format!("a {loose} b {tight} c"),
+                                format!("({loose} a ({tight} b c))"),
+                            ),
+                            (
+                                format!("a {tight} b {loose} c"),
+                                format!("({loose} ({tight} a b) c)"),
+                            ),
+                            (
+                                format!("(a {loose} b) {tight} c"),
+                                format!("({tight} ({loose} a b) c)"),
+                            ),
+                        ];
+
+                        for (source, expected) in cases {
+                            let expression = parse_expression(&source).unwrap();
+                            assert_eq!(sexpr(&expression), expected, "while parsing `{source}`");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parses_unary_and_assignment() {
+        let cases = [
+            ("!a", "(! a)"),
+            ("!!a", "(! (! a))"),
+            ("!a == b", "(== (! a) b)"),
+            ("!(a == b)", "(! (== a b))"),
+            ("!a * b", "(* (! a) b)"),
+            ("!f(x)", "(! (call f [x]))"),
+            ("a = 1", "(= a 1)"),
+            ("a = b = c", "(= a (= b c))"),
+            ("a = b || c", "(= a (|| b c))"),
+            ("a = !b && c", "(= a (&& (! b) c))"),
+            ("f(a = 1)", "(call f [(= a 1)])"),
+        ];
+
+        for (source, expected) in cases {
+            let expression = parse_expression(source).unwrap();
+            assert_eq!(sexpr(&expression), expected, "while parsing `{source}`");
+        }
+    }
+
+    #[test]
+    fn spans_cover_the_whole_expression() {
+        let cases = [
+            ("1 + 2", 0..5),
+            ("a = b * c", 0..9),
+            ("!x", 0..2),
+            ("f(1, 2)", 0..7),
+        ];
+
+        for (source, expected) in cases {
+            let expression = parse_expression(source).unwrap();
+            assert_eq!(
+                expression.span(),
+                Range::from(expected),
+                "while parsing `{source}`"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        let cases = ["1 +", "(1 + 2", "a + b = c", "1 = 2", "f(1,)", "*"];
+
+        for source in cases {
+            let mut parser = Parser::new(Lexer::new(source));
+            let result = parser.expect_expression(0);
+            assert!(result.is_err(), "`{source}` should not parse");
+        }
+    }
+
+    #[test]
+    fn reports_invalid_assignment_target() {
+        let Err(error) = parse_expression("a + b = c") else {
+            panic!("`a + b = c` should not parse");
+        };
+        assert!(
+            matches!(error, ParseError::InvalidAssignmentTarget(span) if span == Range::from(0..5))
+        );
     }
 }
@@ -0,0 +1,334 @@
+//! The state behind `invariant repl`.
+//!
+//! Every input is a mix of function definitions and statements. Functions
+//! and the variables bound by top-level `let`s persist between inputs, and
+//! the value of every expression statement is printed. A final expression
+//! may leave out its `;`.
+
+use crate::ast::{Item, Statement, TreePrinter};
+use crate::diagnostics::{Diagnostic, Label, Renderer, Source};
+use crate::interpret::{Interpreter, Value};
+use crate::lexer::Lexer;
+use crate::parser::{Entry, Parser};
+use crate::serialize;
+use crate::token::Token;
+use crate::types::{self, Type};
+use std::io::{self, Write};
+
+pub const HELP: &str = "\
+:ast      print the syntax tree of the last input
+:tokens   print the tokens of the last input
+:type     print the types of the last input
+:help     print this help
+:quit     leave the REPL
+";
+
+/// The name diagnostics use for REPL input.
+const NAME: &str = "<repl>";
+
+/// Evaluates REPL input, writing results, program output and diagnostics to
+/// `out`.
+pub struct Repl<W> {
+    interpreter: Interpreter<W>,
+    renderer: Renderer,
+    /// The last input that was not a command, for `:ast`, `:tokens` and
+    /// `:type`.
+    last: Option<String>,
+}
+
+/// Whether `input` can be evaluated, or is still inside a `{` or a block
+/// comment and needs more lines.
+pub fn is_complete(input: &str) -> bool {
+    let mut depth = 0isize;
+
+    for (token, _) in Lexer::new(input) {
+        match token {
+            Token::LBrace => depth += 1,
+            Token::RBrace => depth -= 1,
+            Token::UnterminatedComment => return false,
+            _ => {}
+        }
+    }
+
+    depth <= 0
+}
+
+impl<W: Write + Send> Repl<W> {
+    pub fn new(out: W, renderer: Renderer) -> Self {
+        Self {
+            interpreter: Interpreter::new(out),
+            renderer,
+            last: None,
+        }
+    }
+
+    pub fn into_output(self) -> W {
+        self.interpreter.into_output()
+    }
+
+    /// Handles one complete input, a command or code. Returns `false` once
+    /// the input asks to quit.
+    pub fn handle(&mut self, input: &str) -> io::Result<bool> {
+        match input.trim() {
+            "" => {}
+            ":quit" | ":q" => return Ok(false),
+            ":help" => write!(self.out(), "{HELP}")?,
+            ":ast" => self.show(Self::ast)?,
+            ":tokens" => self.show(Self::tokens)?,
+            ":type" => self.show(Self::types)?,
+            command if command.starts_with(':') => {
+                writeln!(self.out(), "unknown command `{command}`, see `:help`")?
+            }
+            _ => self.evaluate(input)?,
+        }
+
+        Ok(true)
+    }
+
+    fn out(&mut self) -> &mut W {
+        self.interpreter.output()
+    }
+
+    /// Runs a `:` command that works on the last input.
+    fn show(&mut self, command: fn(&mut Self, &str) -> io::Result<()>) -> io::Result<()> {
+        match self.last.clone() {
+            Some(input) => command(self, &input),
+            None => writeln!(self.out(), "nothing has been entered yet"),
+        }
+    }
+
+    fn report(&mut self, input: &str, diagnostic: Diagnostic) -> io::Result<()> {
+        let rendered = self.renderer.render(&diagnostic, &Source::new(NAME, input));
+        writeln!(self.out(), "{rendered}")
+    }
+
+    /// Parses `input`, reporting any syntax errors.
+    fn parse(&mut self, input: &str) -> io::Result<Option<Vec<Entry<Item, Statement>>>> {
+        let (entries, errors) = Parser::new(Lexer::new(input)).parse_entries();
+        if errors.is_empty() {
+            return Ok(Some(entries));
+        }
+
+        let source = Source::new(NAME, input);
+        for error in &errors {
+            self.report(input, Diagnostic::from_parse_error(error, &source))?;
+        }
+
+        Ok(None)
+    }
+
+    fn evaluate(&mut self, input: &str) -> io::Result<()> {
+        let input = terminate(input);
+        self.last = Some(input.clone());
+        let Some(entries) = self.parse(&input)? else {
+            return Ok(());
+        };
+
+        for entry in entries {
+            let statement = match entry {
+                Entry::Item(item) => {
+                    self.interpreter.define(item);
+                    continue;
+                }
+                Entry::Statement(statement) => statement,
+            };
+
+            match self.interpreter.evaluate_statement(&statement) {
+                Ok(Value::Unit) => {}
+                Ok(value) => writeln!(self.out(), "{value}")?,
+                Err(error) => {
+                    // later statements may depend on the one that failed
+                    return self.report(&input, Diagnostic::from_runtime_error(&error));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn ast(&mut self, input: &str) -> io::Result<()> {
+        let Some(entries) = self.parse(input)? else {
+            return Ok(());
+        };
+
+        for entry in entries {
+            let printed = match entry {
+                Entry::Item(item) => TreePrinter::new(false).print_item(&item),
+                Entry::Statement(statement) => TreePrinter::new(false).print_statement(&statement),
+            };
+            write!(self.out(), "{printed}")?;
+        }
+
+        Ok(())
+    }
+
+    fn tokens(&mut self, input: &str) -> io::Result<()> {
+        let tokens = Lexer::new(input).collect::<Vec<_>>();
+        let listing = serialize::tokens_to_text(input, &tokens);
+
+        write!(self.out(), "{listing}")
+    }
+
+    /// Prints the signature of every function in the input and the type of
+    /// every statement. The types of the variables the input uses come from
+    /// their current values, so they reflect the input having run.
+    fn types(&mut self, input: &str) -> io::Result<()> {
+        let Some(entries) = self.parse(input)? else {
+            return Ok(());
+        };
+
+        let mut lines = Vec::new();
+        let mut statements = Vec::new();
+        for entry in &entries {
+            match entry {
+                Entry::Item(item) => match types::infer_function(&self.interpreter, item) {
+                    Ok(signature) => lines.push(format!("{}: {signature}", item.name())),
+                    Err(error) => return self.report_type_error(input, error),
+                },
+                Entry::Statement(statement) => statements.push(statement),
+            }
+        }
+
+        let inferred = match types::infer_statements(&self.interpreter, statements.iter().copied())
+        {
+            Ok(inferred) => inferred,
+            Err(error) => return self.report_type_error(input, error),
+        };
+        for (statement, ty) in statements.into_iter().zip(inferred) {
+            let ty = ty.map_or("?".to_string(), |ty| ty.to_string());
+            lines.push(match statement {
+                Statement::Binding { bind_to, .. } => format!("{bind_to}: {ty}"),
+                _ => ty,
+            });
+        }
+
+        for line in lines {
+            writeln!(self.out(), "{line}")?;
+        }
+
+        Ok(())
+    }
+
+    fn report_type_error(&mut self, input: &str, error: types::TypeError) -> io::Result<()> {
+        let diagnostic = Diagnostic::error(error.to_string()).with_label(
+            Label::primary(error.span).with_message(format!("expected `{}`", error.expected)),
+        );
+
+        self.report(input, diagnostic)
+    }
+}
+
+impl<W> types::Environment for Interpreter<W> {
+    fn variable(&self, name: &str) -> Option<Type> {
+        self.lookup(name).map(Type::of)
+    }
+
+    fn function(&self, name: &str) -> Option<&Item> {
+        Interpreter::function(self, name)
+    }
+}
+
+/// Adds the `;` a final expression may leave out, right after its last
+/// token so that it does not end up in a comment.
+fn terminate(input: &str) -> String {
+    let mut input = input.trim_end().to_string();
+    if let Some((token, span)) = Lexer::new(&input).last()
+        && !matches!(token, Token::Semicolon | Token::RBrace)
+    {
+        input.insert(span.end, ';');
+    }
+
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(inputs: &[&str]) -> String {
+        let mut repl = Repl::new(Vec::new(), Renderer::new(false));
+        for input in inputs {
+            assert!(repl.handle(input).unwrap());
+        }
+
+        String::from_utf8(repl.into_output()).unwrap()
+    }
+
+    #[test]
+    fn keeps_variables_and_functions_between_inputs() {
+        assert_eq!(
+            session(&[
+                "let x = 20;",
+                "fn double(n) { return n * 2; }",
+                "double(x) + 2",
+                "x = x + 1; print(x);",
+                "x == 21",
+            ]),
+            "42\n21\n21\ntrue\n"
+        );
+    }
+
+    #[test]
+    fn waits_for_closing_braces() {
+        assert!(is_complete("let x = 1;"));
+        assert!(!is_complete("fn f() {"));
+        assert!(!is_complete("fn f() { if x { }"));
+        assert!(!is_complete("/* still open"));
+        assert!(is_complete("fn f() {\n    return 1;\n}"));
+        assert!(is_complete("}"));
+    }
+
+    #[test]
+    fn shows_the_stages_of_the_last_input() {
+        assert_eq!(
+            session(&["let a = 1 < 2", ":tokens", ":ast"]),
+            "\
+1:1-1:4   Let        \"let\"
+1:5-1:6   Identifier \"a\"
+1:7-1:8   Equal      \"=\"
+1:9-1:10  Int        \"1\"
+1:11-1:12 Less       \"<\"
+1:13-1:14 Int        \"2\"
+1:14-1:15 Semicolon  \";\"
+Let-Stmt
+|- identifier: a
+|- value: Less-Operation
+          |- lhs: Int-Expression
+                  |- value: 1
+          |- rhs: Int-Expression
+                  |- value: 2
+"
+        );
+        assert_eq!(
+            session(&[
+                "let t = true;",
+                "fn f(n) { return n > 0; } let u = f(1); t && u; 1 + 2",
+                ":type"
+            ]),
+            "true\n\
+             3\n\
+             f: fn(int) -> bool\n\
+             u: bool\n\
+             bool\n\
+             int\n"
+        );
+    }
+
+    #[test]
+    fn reports_errors_and_carries_on() {
+        let output = session(&[
+            "let x = 1 / 0;",
+            "x",
+            "let y = ;",
+            ":nope",
+            "1 + 1 // c",
+            "2 + 2 /* c */",
+        ]);
+
+        assert!(output.contains("error: division by zero"));
+        assert!(output.contains("cannot find variable `x`"));
+        assert!(output.contains("error: expected one of"));
+        assert!(output.contains("unknown command `:nope`"));
+        assert!(output.ends_with("2\n4\n"));
+    }
+}
@@ -0,0 +1,166 @@
+//! Machine-readable forms of the token stream and the AST, for tools that do
+//! not link against this crate.
+//!
+//! Both JSON and S-expressions carry the span of every token and node as byte
+//! offsets into the source. Only the JSON form of the AST can be read back.
+//! Tokens can also be listed as text, with line and column positions.
+
+mod decode;
+mod encode;
+mod json;
+mod sexp;
+mod text;
+
+pub use decode::program_from_json;
+pub use encode::{program_to_json, tokens_to_json};
+pub use json::{Json, JsonError};
+pub use sexp::{program_to_sexp, tokens_to_sexp};
+pub use text::tokens_to_text;
+
+use crate::ast::Program;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeserializeError {
+    Json(JsonError),
+    /// The document is valid JSON but not a valid AST. `path` leads from the
+    /// root to the offending value, like `items[0].body.body[2].value`.
+    Invalid {
+        path: String,
+        message: String,
+    },
+}
+
+impl DeserializeError {
+    fn invalid(path: impl Into<String>, message: impl Into<String>) -> Self {
+        DeserializeError::Invalid {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeserializeError::Json(error) => write!(f, "invalid JSON: {error}"),
+            DeserializeError::Invalid { path, message } if path.is_empty() => f.write_str(message),
+            DeserializeError::Invalid { path, message } => write!(f, "at `{path}`: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl From<JsonError> for DeserializeError {
+    fn from(error: JsonError) -> Self {
+        DeserializeError::Json(error)
+    }
+}
+
+/// Parses `input` as JSON and decodes the program in it.
+pub fn program_from_json_str(input: &str) -> Result<Program, DeserializeError> {
+    program_from_json(&Json::parse(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::TreePrinter;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn parse(source: &str) -> Program {
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+        assert!(errors.is_empty(), "`{source}` failed to parse: {errors:?}");
+
+        program
+    }
+
+    #[test]
+    fn encodes_nodes_with_their_spans() {
+        let program = parse("fn f(a) { return -a + g(1, true); }");
+
+        assert_eq!(
+            program_to_json(&program).to_string(),
+            r#"{"type":"Program","items":[{"type":"Function","span":[0,35],"name":"f","arguments":["a"],"body":{"type":"Block","span":[8,35],"body":[{"type":"Return","span":[10,33],"value":{"type":"Binary","span":[17,32],"operator":"+","lhs":{"type":"Unary","span":[17,19],"operator":"-","operand":{"type":"Reference","span":[18,19],"name":"a"}},"rhs":{"type":"Call","span":[22,32],"name":"g","arguments":[{"type":"Integer","span":[24,25],"value":1},{"type":"Boolean","span":[27,31],"value":true}]}}}]}}]}"#
+        );
+        assert_eq!(
+            program_to_sexp(&program),
+            "(program\n  (fn 0..35 f (a) (block 8..35 (return 10..33 (+ 17..32 (- 17..19 (ref 18..19 a)) \
+             (call 22..32 g (int 24..25 1) (bool 27..31 true)))))))\n"
+        );
+    }
+
+    #[test]
+    fn encodes_tokens_with_their_text() {
+        let source = "let x=10;";
+        let tokens = Lexer::new(source).collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens_to_json(source, &tokens[..2]).to_string(),
+            r#"[{"kind":"Let","text":"let","span":[0,3],"start":{"line":1,"column":1},"end":{"line":1,"column":4}},{"kind":"Identifier","text":"x","span":[4,5],"start":{"line":1,"column":5},"end":{"line":1,"column":6}}]"#
+        );
+        assert_eq!(
+            tokens_to_sexp(source, &tokens),
+            "(tokens\n  (Let 0..3 \"let\")\n  (Identifier 4..5 \"x\")\n  (Equal 5..6 \"=\")\n  \
+             (Int 6..8 \"10\")\n  (Semicolon 8..9 \";\"))\n"
+        );
+    }
+
+    #[test]
+    fn lists_tokens_with_line_and_column_positions() {
+        let source = "let x =\n  10;";
+        let tokens = Lexer::new(source).collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens_to_text(source, &tokens),
+            "\
+1:1-1:4 Let        \"let\"
+1:5-1:6 Identifier \"x\"
+1:7-1:8 Equal      \"=\"
+2:3-2:5 Int        \"10\"
+2:5-2:6 Semicolon  \";\"
+"
+        );
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let source = "fn f(a, b) { let x = a; while !(x >= b) { x = x * 2; } \
+                      if x == 1 || false { f(x, 0); } else {} return -1; }";
+        let program = parse(source);
+        let decoded = program_from_json_str(&format!("{:#}", program_to_json(&program))).unwrap();
+
+        assert_eq!(
+            TreePrinter::new(true).print_program(&decoded),
+            TreePrinter::new(true).print_program(&program)
+        );
+        assert_eq!(program_to_json(&decoded), program_to_json(&program));
+    }
+
+    #[test]
+    fn decoding_reports_where_the_tree_is_invalid() {
+        let error = |input: &str| program_from_json_str(input).unwrap_err().to_string();
+
+        assert_eq!(error("[1"), "invalid JSON: expected `,` or `]` at byte 2");
+        assert_eq!(
+            error(r#"{"type":"Block"}"#),
+            "expected a `Program`, found `Block`"
+        );
+        assert_eq!(
+            error(
+                r#"{"type":"Program","items":[{"type":"Function","span":[0,9],"name":"f","arguments":[],
+                    "body":{"type":"Block","span":[5,9],"body":[{"type":"Return","span":[9,5]}]}}]}"#
+            ),
+            "at `items[0].body.body[0]`: missing field `value`"
+        );
+        assert_eq!(
+            error(
+                r#"{"type":"Program","items":[{"type":"Function","span":[0,9],"name":"f","arguments":[],
+                    "body":{"type":"Expression","span":[5,9],"expression":{"type":"Unary","span":[5,9],"operator":"~"}}}]}"#
+            ),
+            "at `items[0].body.expression.operator`: `~` is not a unary operator"
+        );
+    }
+}
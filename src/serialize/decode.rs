@@ -0,0 +1,362 @@
+use crate::ast::id::NodeId;
+use crate::ast::{
+    BinaryOperation, BinaryOperator, Expression, Item, Operation, Program, Statement,
+    UnaryOperation, UnaryOperator,
+};
+use crate::serialize::{DeserializeError, Json};
+use std::range::Range;
+
+/// Decodes a program in the format written by `program_to_json`.
+///
+/// Nodes are numbered children first, like the parser does, so the result can
+/// be handed to any pass that keeps side tables.
+pub fn program_from_json(json: &Json) -> Result<Program, DeserializeError> {
+    let mut decoder = Decoder { next_id: 0 };
+    let path = String::new();
+    decoder.expect_type(json, &path, "Program")?;
+
+    let items = decoder
+        .array(json, &path, "items")?
+        .iter()
+        .enumerate()
+        .map(|(index, item)| decoder.item(item, &format!("items[{index}]")))
+        .collect::<Result<_, _>>()?;
+
+    Ok(Program::new(items))
+}
+
+struct Decoder {
+    next_id: u32,
+}
+
+impl Decoder {
+    fn node_id(&mut self) -> NodeId {
+        let id = NodeId::new(self.next_id);
+        self.next_id += 1;
+
+        id
+    }
+
+    fn item(&mut self, json: &Json, path: &str) -> Result<Item, DeserializeError> {
+        self.expect_type(json, path, "Function")?;
+
+        let name = self.string(json, path, "name")?;
+        let arguments = self
+            .array(json, path, "arguments")?
+            .iter()
+            .enumerate()
+            .map(|(index, argument)| match argument {
+                Json::String(argument) => Ok(argument.clone()),
+                _ => Err(DeserializeError::invalid(
+                    format!("{path}.arguments[{index}]"),
+                    "expected a string",
+                )),
+            })
+            .collect::<Result<_, _>>()?;
+        let body = self.statement(self.field(json, path, "body")?, &join(path, "body"))?;
+
+        Ok(Item::Function {
+            name,
+            arguments,
+            body,
+            span: self.span(json, path)?,
+            id: self.node_id(),
+        })
+    }
+
+    fn statement(&mut self, json: &Json, path: &str) -> Result<Statement, DeserializeError> {
+        let statement = match self.node_type(json, path)? {
+            "Block" => {
+                let body = self
+                    .array(json, path, "body")?
+                    .iter()
+                    .enumerate()
+                    .map(|(index, statement)| {
+                        self.statement(statement, &format!("{path}.body[{index}]"))
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                Statement::Block {
+                    body,
+                    span: self.span(json, path)?,
+                    id: self.node_id(),
+                }
+            }
+            "Binding" => Statement::Binding {
+                bind_to: self.string(json, path, "bind_to")?,
+                value: self.expression_field(json, path, "value")?,
+                span: self.span(json, path)?,
+                id: self.node_id(),
+            },
+            "Expression" => Statement::Expression {
+                expression: self.expression_field(json, path, "expression")?,
+                span: self.span(json, path)?,
+                id: self.node_id(),
+            },
+            "Return" => Statement::Return {
+                value: self.expression_field(json, path, "value")?,
+                span: self.span(json, path)?,
+                id: self.node_id(),
+            },
+            "Branch" => {
+                let condition = self.expression_field(json, path, "condition")?;
+                let then = self.statement(self.field(json, path, "then")?, &join(path, "then"))?;
+                let otherwise = match json.get("otherwise") {
+                    None | Some(Json::Null) => None,
+                    Some(otherwise) => Some(Box::new(
+                        self.statement(otherwise, &join(path, "otherwise"))?,
+                    )),
+                };
+
+                Statement::Branch {
+                    condition,
+                    then: Box::new(then),
+                    otherwise,
+                    span: self.span(json, path)?,
+                    id: self.node_id(),
+                }
+            }
+            "While" => {
+                let condition = self.expression_field(json, path, "condition")?;
+                let body = self.statement(self.field(json, path, "body")?, &join(path, "body"))?;
+
+                Statement::While {
+                    condition,
+                    body: Box::new(body),
+                    span: self.span(json, path)?,
+                    id: self.node_id(),
+                }
+            }
+            "Error" => Statement::Error {
+                span: self.span(json, path)?,
+                id: self.node_id(),
+            },
+            other => {
+                return Err(DeserializeError::invalid(
+                    path,
+                    format!("`{other}` is not a statement"),
+                ));
+            }
+        };
+
+        Ok(statement)
+    }
+
+    fn expression_field(
+        &mut self,
+        json: &Json,
+        path: &str,
+        key: &str,
+    ) -> Result<Expression, DeserializeError> {
+        self.expression(self.field(json, path, key)?, &join(path, key))
+    }
+
+    fn expression(&mut self, json: &Json, path: &str) -> Result<Expression, DeserializeError> {
+        let expression = match self.node_type(json, path)? {
+            "Integer" => Expression::Integer {
+                value: match self.field(json, path, "value")? {
+                    Json::Number(value) => *value,
+                    _ => {
+                        return Err(DeserializeError::invalid(
+                            join(path, "value"),
+                            "expected an integer",
+                        ));
+                    }
+                },
+                span: self.span(json, path)?,
+                id: self.node_id(),
+            },
+            "Boolean" => Expression::Boolean {
+                value: match self.field(json, path, "value")? {
+                    Json::Bool(value) => *value,
+                    _ => {
+                        return Err(DeserializeError::invalid(
+                            join(path, "value"),
+                            "expected a boolean",
+                        ));
+                    }
+                },
+                span: self.span(json, path)?,
+                id: self.node_id(),
+            },
+            "Reference" => Expression::Reference {
+                name: self.string(json, path, "name")?,
+                span: self.span(json, path)?,
+                id: self.node_id(),
+            },
+            "Assignment" => Expression::Assignment {
+                assign_to: self.string(json, path, "assign_to")?,
+                value: Box::new(self.expression_field(json, path, "value")?),
+                span: self.span(json, path)?,
+                id: self.node_id(),
+            },
+            "Unary" => {
+                let symbol = self.string(json, path, "operator")?;
+                let operator = UnaryOperator::ALL
+                    .into_iter()
+                    .find(|operator| operator.symbol() == symbol)
+                    .ok_or_else(|| {
+                        DeserializeError::invalid(
+                            join(path, "operator"),
+                            format!("`{symbol}` is not a unary operator"),
+                        )
+                    })?;
+                let operand = self.expression_field(json, path, "operand")?;
+
+                Expression::Operation {
+                    operation: Operation::Unary(UnaryOperation::new(operator, Box::new(operand))),
+                    span: self.span(json, path)?,
+                    id: self.node_id(),
+                }
+            }
+            "Binary" => {
+                let symbol = self.string(json, path, "operator")?;
+                let operator = BinaryOperator::ALL
+                    .into_iter()
+                    .find(|operator| operator.symbol() == symbol)
+                    .ok_or_else(|| {
+                        DeserializeError::invalid(
+                            join(path, "operator"),
+                            format!("`{symbol}` is not a binary operator"),
+                        )
+                    })?;
+                let lhs = self.expression_field(json, path, "lhs")?;
+                let rhs = self.expression_field(json, path, "rhs")?;
+
+                Expression::Operation {
+                    operation: Operation::Binary(BinaryOperation::new(
+                        operator,
+                        Box::new(lhs),
+                        Box::new(rhs),
+                    )),
+                    span: self.span(json, path)?,
+                    id: self.node_id(),
+                }
+            }
+            "Call" => {
+                let name = self.string(json, path, "name")?;
+                let arguments = self
+                    .array(json, path, "arguments")?
+                    .iter()
+                    .enumerate()
+                    .map(|(index, argument)| {
+                        self.expression(argument, &format!("{path}.arguments[{index}]"))
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                Expression::Call {
+                    name,
+                    arguments,
+                    span: self.span(json, path)?,
+                    id: self.node_id(),
+                }
+            }
+            "Error" => Expression::Error {
+                span: self.span(json, path)?,
+                id: self.node_id(),
+            },
+            other => {
+                return Err(DeserializeError::invalid(
+                    path,
+                    format!("`{other}` is not an expression"),
+                ));
+            }
+        };
+
+        Ok(expression)
+    }
+
+    fn field<'j>(
+        &self,
+        json: &'j Json,
+        path: &str,
+        key: &str,
+    ) -> Result<&'j Json, DeserializeError> {
+        match json {
+            Json::Object(_) => json
+                .get(key)
+                .ok_or_else(|| DeserializeError::invalid(path, format!("missing field `{key}`"))),
+            _ => Err(DeserializeError::invalid(path, "expected an object")),
+        }
+    }
+
+    fn node_type<'j>(&self, json: &'j Json, path: &str) -> Result<&'j str, DeserializeError> {
+        match self.field(json, path, "type")? {
+            Json::String(kind) => Ok(kind),
+            _ => Err(DeserializeError::invalid(
+                join(path, "type"),
+                "expected a string",
+            )),
+        }
+    }
+
+    fn expect_type(&self, json: &Json, path: &str, expected: &str) -> Result<(), DeserializeError> {
+        match self.node_type(json, path)? {
+            kind if kind == expected => Ok(()),
+            kind => Err(DeserializeError::invalid(
+                path,
+                format!("expected a `{expected}`, found `{kind}`"),
+            )),
+        }
+    }
+
+    fn string(&self, json: &Json, path: &str, key: &str) -> Result<String, DeserializeError> {
+        match self.field(json, path, key)? {
+            Json::String(value) => Ok(value.clone()),
+            _ => Err(DeserializeError::invalid(
+                join(path, key),
+                "expected a string",
+            )),
+        }
+    }
+
+    fn array<'j>(
+        &self,
+        json: &'j Json,
+        path: &str,
+        key: &str,
+    ) -> Result<&'j [Json], DeserializeError> {
+        match self.field(json, path, key)? {
+            Json::Array(values) => Ok(values),
+            _ => Err(DeserializeError::invalid(
+                join(path, key),
+                "expected an array",
+            )),
+        }
+    }
+
+    fn span(&self, json: &Json, path: &str) -> Result<Range<usize>, DeserializeError> {
+        let offset = |value: &Json| match value {
+            Json::Number(value) => usize::try_from(*value).ok(),
+            _ => None,
+        };
+
+        match self.field(json, path, "span")? {
+            Json::Array(offsets) => match offsets.as_slice() {
+                [start, end] => match (offset(start), offset(end)) {
+                    (Some(start), Some(end)) if start <= end => Ok(Range::from(start..end)),
+                    _ => Err(DeserializeError::invalid(
+                        join(path, "span"),
+                        "expected a start offset that is not after the end offset",
+                    )),
+                },
+                _ => Err(DeserializeError::invalid(
+                    join(path, "span"),
+                    "expected `[start, end]`",
+                )),
+            },
+            _ => Err(DeserializeError::invalid(
+                join(path, "span"),
+                "expected `[start, end]`",
+            )),
+        }
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
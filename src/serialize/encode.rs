@@ -0,0 +1,240 @@
+use crate::ast::{Expression, Item, Operation, Program, Statement};
+use crate::diagnostics::{Location, Source};
+use crate::serialize::Json;
+use crate::token::Token;
+use std::range::Range;
+
+/// Encodes the program as a JSON object. Every node is an object with a
+/// `type` and a `span`, followed by its fields as named in `ast`.
+pub fn program_to_json(program: &Program) -> Json {
+    Json::object([
+        ("type", Json::String("Program".to_string())),
+        (
+            "items",
+            Json::Array(program.items().iter().map(item).collect()),
+        ),
+    ])
+}
+
+/// Encodes tokens as an array of `{"kind", "text", "span", "start", "end"}`
+/// objects, where `text` is the token's lexeme in `source` and `start` and
+/// `end` are the `{"line", "column"}` of its span, both one-based.
+pub fn tokens_to_json(source: &str, tokens: &[(Token, Range<usize>)]) -> Json {
+    let positions = Source::new("", source);
+
+    Json::Array(
+        tokens
+            .iter()
+            .map(|(token, span)| {
+                Json::object([
+                    ("kind", Json::String(format!("{:?}", token.kind()))),
+                    ("text", Json::String(source[*span].to_string())),
+                    ("span", self::span(*span)),
+                    ("start", location(positions.location(span.start))),
+                    ("end", location(positions.location(span.end))),
+                ])
+            })
+            .collect(),
+    )
+}
+
+fn location(location: Location) -> Json {
+    let number =
+        |number: usize| Json::Number(i64::try_from(number).expect("positions fit into `i64`"));
+
+    Json::object([
+        ("line", number(location.line)),
+        ("column", number(location.column)),
+    ])
+}
+
+fn span(span: Range<usize>) -> Json {
+    let offset =
+        |offset: usize| Json::Number(i64::try_from(offset).expect("offsets fit into `i64`"));
+
+    Json::Array(vec![offset(span.start), offset(span.end)])
+}
+
+fn node<const N: usize>(kind: &str, span: Range<usize>, fields: [(&str, Json); N]) -> Json {
+    let mut members = vec![
+        ("type".to_string(), Json::String(kind.to_string())),
+        ("span".to_string(), self::span(span)),
+    ];
+    members.extend(
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value)),
+    );
+
+    Json::Object(members)
+}
+
+fn name(name: &str) -> Json {
+    Json::String(name.to_string())
+}
+
+fn item(item: &Item) -> Json {
+    match item {
+        Item::Function {
+            name,
+            arguments,
+            body,
+            span,
+            ..
+        } => node(
+            "Function",
+            *span,
+            [
+                ("name", self::name(name)),
+                (
+                    "arguments",
+                    Json::Array(
+                        arguments
+                            .iter()
+                            .map(|argument| self::name(argument))
+                            .collect(),
+                    ),
+                ),
+                ("body", statement(body)),
+            ],
+        ),
+    }
+}
+
+fn statement(statement: &Statement) -> Json {
+    match statement {
+        Statement::Block { body, span, .. } => node(
+            "Block",
+            *span,
+            [(
+                "body",
+                Json::Array(body.iter().map(self::statement).collect()),
+            )],
+        ),
+        Statement::Binding {
+            bind_to,
+            value,
+            span,
+            ..
+        } => node(
+            "Binding",
+            *span,
+            [("bind_to", name(bind_to)), ("value", expression(value))],
+        ),
+        Statement::Expression {
+            expression, span, ..
+        } => node(
+            "Expression",
+            *span,
+            [("expression", self::expression(expression))],
+        ),
+        Statement::Return { value, span, .. } => {
+            node("Return", *span, [("value", expression(value))])
+        }
+        Statement::Branch {
+            condition,
+            then,
+            otherwise,
+            span,
+            ..
+        } => node(
+            "Branch",
+            *span,
+            [
+                ("condition", expression(condition)),
+                ("then", self::statement(then)),
+                (
+                    "otherwise",
+                    otherwise.as_deref().map_or(Json::Null, self::statement),
+                ),
+            ],
+        ),
+        Statement::While {
+            condition,
+            body,
+            span,
+            ..
+        } => node(
+            "While",
+            *span,
+            [
+                ("condition", expression(condition)),
+                ("body", self::statement(body)),
+            ],
+        ),
+        Statement::Error { span, .. } => node("Error", *span, []),
+    }
+}
+
+fn expression(expression: &Expression) -> Json {
+    match expression {
+        Expression::Integer { value, span, .. } => {
+            node("Integer", *span, [("value", Json::Number(*value))])
+        }
+        Expression::Boolean { value, span, .. } => {
+            node("Boolean", *span, [("value", Json::Bool(*value))])
+        }
+        Expression::Reference { name, span, .. } => {
+            node("Reference", *span, [("name", self::name(name))])
+        }
+        Expression::Assignment {
+            assign_to,
+            value,
+            span,
+            ..
+        } => node(
+            "Assignment",
+            *span,
+            [
+                ("assign_to", name(assign_to)),
+                ("value", self::expression(value)),
+            ],
+        ),
+        Expression::Operation {
+            operation: Operation::Unary(operation),
+            span,
+            ..
+        } => node(
+            "Unary",
+            *span,
+            [
+                ("operator", name(operation.operator().symbol())),
+                ("operand", self::expression(operation.operand())),
+            ],
+        ),
+        Expression::Operation {
+            operation: Operation::Binary(operation),
+            span,
+            ..
+        } => {
+            let (lhs, rhs) = operation.operands();
+
+            node(
+                "Binary",
+                *span,
+                [
+                    ("operator", name(operation.operator().symbol())),
+                    ("lhs", self::expression(lhs)),
+                    ("rhs", self::expression(rhs)),
+                ],
+            )
+        }
+        Expression::Call {
+            name,
+            arguments,
+            span,
+            ..
+        } => node(
+            "Call",
+            *span,
+            [
+                ("name", self::name(name)),
+                (
+                    "arguments",
+                    Json::Array(arguments.iter().map(self::expression).collect()),
+                ),
+            ],
+        ),
+        Expression::Error { span, .. } => node("Error", *span, []),
+    }
+}
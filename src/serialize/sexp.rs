@@ -0,0 +1,186 @@
+use crate::ast::{Expression, Item, Operation, Program, Statement};
+use crate::token::Token;
+use std::fmt::Write;
+use std::range::Range;
+
+/// Writes the program as one S-expression per item. Every node is a list of
+/// its tag, its span as `start..end` and its children, so `let x = 1 + y;`
+/// becomes `(let 0..14 x (+ 8..13 (int 8..9 1) (ref 12..13 y)))`.
+pub fn program_to_sexp(program: &Program) -> String {
+    let mut out = String::from("(program");
+    for item in program.items() {
+        out.push_str("\n  ");
+        self::item(&mut out, item);
+    }
+    out.push_str(")\n");
+
+    out
+}
+
+/// Writes one `(kind start..end "text")` list per token, one per line.
+pub fn tokens_to_sexp(source: &str, tokens: &[(Token, Range<usize>)]) -> String {
+    let mut out = String::from("(tokens");
+    for (token, span) in tokens {
+        let _ = write!(
+            out,
+            "\n  ({:?} {}..{} {:?})",
+            token.kind(),
+            span.start,
+            span.end,
+            &source[*span]
+        );
+    }
+    out.push_str(")\n");
+
+    out
+}
+
+fn open(out: &mut String, tag: &str, span: Range<usize>) {
+    let _ = write!(out, "({tag} {}..{}", span.start, span.end);
+}
+
+fn item(out: &mut String, item: &Item) {
+    match item {
+        Item::Function {
+            name,
+            arguments,
+            body,
+            span,
+            ..
+        } => {
+            open(out, "fn", *span);
+            let _ = write!(out, " {name} ({})", arguments.join(" "));
+            out.push(' ');
+            statement(out, body);
+            out.push(')');
+        }
+    }
+}
+
+fn statement(out: &mut String, statement: &Statement) {
+    match statement {
+        Statement::Block { body, span, .. } => {
+            open(out, "block", *span);
+            for statement in body {
+                out.push(' ');
+                self::statement(out, statement);
+            }
+        }
+        Statement::Binding {
+            bind_to,
+            value,
+            span,
+            ..
+        } => {
+            open(out, "let", *span);
+            let _ = write!(out, " {bind_to} ");
+            expression(out, value);
+        }
+        Statement::Expression {
+            expression, span, ..
+        } => {
+            open(out, "expr", *span);
+            out.push(' ');
+            self::expression(out, expression);
+        }
+        Statement::Return { value, span, .. } => {
+            open(out, "return", *span);
+            out.push(' ');
+            expression(out, value);
+        }
+        Statement::Branch {
+            condition,
+            then,
+            otherwise,
+            span,
+            ..
+        } => {
+            open(out, "if", *span);
+            out.push(' ');
+            expression(out, condition);
+            out.push(' ');
+            self::statement(out, then);
+            if let Some(otherwise) = otherwise {
+                out.push(' ');
+                self::statement(out, otherwise);
+            }
+        }
+        Statement::While {
+            condition,
+            body,
+            span,
+            ..
+        } => {
+            open(out, "while", *span);
+            out.push(' ');
+            expression(out, condition);
+            out.push(' ');
+            self::statement(out, body);
+        }
+        Statement::Error { span, .. } => open(out, "error", *span),
+    }
+    out.push(')');
+}
+
+fn expression(out: &mut String, expression: &Expression) {
+    match expression {
+        Expression::Integer { value, span, .. } => {
+            open(out, "int", *span);
+            let _ = write!(out, " {value}");
+        }
+        Expression::Boolean { value, span, .. } => {
+            open(out, "bool", *span);
+            let _ = write!(out, " {value}");
+        }
+        Expression::Reference { name, span, .. } => {
+            open(out, "ref", *span);
+            let _ = write!(out, " {name}");
+        }
+        Expression::Assignment {
+            assign_to,
+            value,
+            span,
+            ..
+        } => {
+            open(out, "assign", *span);
+            let _ = write!(out, " {assign_to} ");
+            self::expression(out, value);
+        }
+        Expression::Operation {
+            operation: Operation::Unary(operation),
+            span,
+            ..
+        } => {
+            open(out, operation.operator().symbol(), *span);
+            out.push(' ');
+            self::expression(out, operation.operand());
+        }
+        Expression::Operation {
+            operation: Operation::Binary(operation),
+            span,
+            ..
+        } => {
+            let (lhs, rhs) = operation.operands();
+            open(out, operation.operator().symbol(), *span);
+            out.push(' ');
+            self::expression(out, lhs);
+            out.push(' ');
+            self::expression(out, rhs);
+        }
+        Expression::Call {
+            name,
+            arguments,
+            span,
+            ..
+        } => {
+            open(out, "call", *span);
+            let _ = write!(out, " {name}");
+            for argument in arguments {
+                out.push(' ');
+                self::expression(out, argument);
+            }
+        }
+        Expression::Error { span, .. } => open(out, "error", *span),
+    }
+    out.push(')');
+}
@@ -0,0 +1,38 @@
+use crate::diagnostics::Source;
+use crate::token::Token;
+use std::fmt::Write;
+use std::range::Range;
+
+/// Lists the tokens one per line as `line:column-line:column Kind "text"`,
+/// with the columns of the listing aligned. The end is the position right
+/// behind the token, so `let` at the start of a file is `1:1-1:4`.
+pub fn tokens_to_text(source: &str, tokens: &[(Token, Range<usize>)]) -> String {
+    let positions = Source::new("", source);
+    let rows = tokens
+        .iter()
+        .map(|(token, span)| {
+            let start = positions.location(span.start);
+            let end = positions.location(span.end);
+            let position = format!(
+                "{}:{}-{}:{}",
+                start.line, start.column, end.line, end.column
+            );
+
+            (position, format!("{:?}", token.kind()), &source[*span])
+        })
+        .collect::<Vec<_>>();
+
+    let position_width = rows.iter().map(|(position, ..)| position.len()).max();
+    let kind_width = rows.iter().map(|(_, kind, _)| kind.len()).max();
+    let (position_width, kind_width) = (position_width.unwrap_or(0), kind_width.unwrap_or(0));
+
+    let mut out = String::new();
+    for (position, kind, text) in rows {
+        let _ = writeln!(
+            out,
+            "{position:position_width$} {kind:kind_width$} {text:?}"
+        );
+    }
+
+    out
+}
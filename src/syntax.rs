@@ -0,0 +1,48 @@
+//! A lossless concrete syntax tree.
+//!
+//! Unlike `ast`, the tree keeps every byte of the input: whitespace and
+//! comments are stored as trivia on the neighbouring tokens, and input the
+//! grammar does not expect ends up in `Error` nodes instead of being dropped.
+//! Printing a tree therefore reproduces its source exactly.
+//!
+//! The tree comes in two layers. Green nodes are immutable, know only their
+//! width and can be shared between trees. Red nodes wrap them on demand with
+//! their parent and absolute position. The `typed` module puts a typed view
+//! on top of the red nodes.
+
+mod green;
+mod parse;
+mod red;
+pub mod typed;
+
+pub use green::{GreenElement, GreenNode, GreenToken, Trivia, TriviaKind};
+pub use parse::parse;
+pub use red::{SyntaxElement, SyntaxNode, SyntaxToken};
+
+/// The kind of an inner node of the tree. Tokens are identified by their
+/// `TokenKind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    SourceFile,
+    Function,
+    ParameterList,
+    Block,
+
+    LetStatement,
+    ExpressionStatement,
+    ReturnStatement,
+    IfStatement,
+    WhileStatement,
+
+    Literal,
+    Reference,
+    ParenExpression,
+    PrefixExpression,
+    BinaryExpression,
+    AssignmentExpression,
+    CallExpression,
+    ArgumentList,
+
+    /// Tokens the grammar did not expect at their position.
+    Error,
+}
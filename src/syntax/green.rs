@@ -0,0 +1,154 @@
+use crate::syntax::NodeKind;
+use crate::token::TokenKind;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    /// A `//` comment, without the line break that ends it.
+    LineComment,
+    BlockComment,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+}
+
+/// A token with the trivia around it.
+///
+/// A token owns the trivia that follows it up to the end of its line. All
+/// other trivia belongs to the next token, which is why every tree ends with
+/// an `Eof` token.
+#[derive(Debug, PartialEq)]
+pub struct GreenToken {
+    kind: TokenKind,
+    text: String,
+    leading: Vec<Trivia>,
+    trailing: Vec<Trivia>,
+}
+
+impl GreenToken {
+    pub fn new(kind: TokenKind, text: String, leading: Vec<Trivia>, trailing: Vec<Trivia>) -> Self {
+        Self {
+            kind,
+            text,
+            leading,
+            trailing,
+        }
+    }
+
+    pub fn kind(&self) -> &TokenKind {
+        &self.kind
+    }
+
+    /// The text of the token itself, without trivia.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn leading(&self) -> &[Trivia] {
+        &self.leading
+    }
+
+    pub fn trailing(&self) -> &[Trivia] {
+        &self.trailing
+    }
+
+    pub fn leading_width(&self) -> usize {
+        self.leading.iter().map(|trivia| trivia.text.len()).sum()
+    }
+
+    /// The length of the token in bytes, including its trivia.
+    pub fn width(&self) -> usize {
+        let trailing = self
+            .trailing
+            .iter()
+            .map(|trivia| trivia.text.len())
+            .sum::<usize>();
+
+        self.leading_width() + self.text.len() + trailing
+    }
+}
+
+impl fmt::Display for GreenToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for trivia in &self.leading {
+            f.write_str(&trivia.text)?;
+        }
+        f.write_str(&self.text)?;
+        for trivia in &self.trailing {
+            f.write_str(&trivia.text)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+    pub fn width(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.width(),
+            GreenElement::Token(token) => token.width(),
+        }
+    }
+}
+
+impl fmt::Display for GreenElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GreenElement::Node(node) => node.fmt(f),
+            GreenElement::Token(token) => token.fmt(f),
+        }
+    }
+}
+
+/// An inner node. It does not know where it is, so equal subtrees can be
+/// shared.
+#[derive(Debug, PartialEq)]
+pub struct GreenNode {
+    kind: NodeKind,
+    width: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: NodeKind, children: Vec<GreenElement>) -> Self {
+        Self {
+            kind,
+            width: children.iter().map(GreenElement::width).sum(),
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    /// The length of the node in bytes, including all trivia.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+}
+
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for child in &self.children {
+            child.fmt(f)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,536 @@
+//! Builds the concrete syntax tree from the token stream.
+//!
+//! The grammar is the one of `parser`, but the parser here never gives up:
+//! missing tokens are simply absent from their node, and unexpected tokens are
+//! wrapped in `Error` nodes. Reporting errors is left to `parser`.
+
+use crate::lexer::Lexer;
+use crate::syntax::{
+    GreenElement, GreenNode, GreenToken, NodeKind, SyntaxNode, Trivia, TriviaKind,
+};
+use crate::token::TokenKind;
+use std::rc::Rc;
+
+pub fn parse(source: &str) -> SyntaxNode {
+    let mut parser = Parser {
+        tokens: tokenize(source),
+        position: 0,
+        builder: Builder::default(),
+    };
+    parser.source_file();
+
+    SyntaxNode::new_root(Rc::new(parser.builder.finish()))
+}
+
+/// Splits `source` into tokens and attaches every byte between them to one of
+/// them as trivia. The last token is always `Eof`.
+fn tokenize(source: &str) -> Vec<GreenToken> {
+    let mut lexer = Lexer::new(source);
+    let mut spans = lexer
+        .by_ref()
+        .map(|(token, span)| (token.kind(), span))
+        .collect::<Vec<_>>();
+    let mut comments = lexer.comments().iter().peekable();
+    spans.push((TokenKind::Eof, (source.len()..source.len()).into()));
+
+    let mut tokens = Vec::with_capacity(spans.len());
+    let mut leading = Vec::new();
+    let mut end = 0;
+
+    for (index, (_, span)) in spans.iter().enumerate() {
+        // everything between the previous token and this one
+        let mut gap = Vec::new();
+        let mut position = end;
+        while let Some(comment) = comments.next_if(|comment| comment.end <= span.start) {
+            if position < comment.start {
+                gap.push(trivia(
+                    TriviaKind::Whitespace,
+                    &source[position..comment.start],
+                ));
+            }
+            let kind = if source[*comment].starts_with("//") {
+                TriviaKind::LineComment
+            } else {
+                TriviaKind::BlockComment
+            };
+            gap.push(trivia(kind, &source[*comment]));
+            position = comment.end;
+        }
+        if position < span.start {
+            gap.push(trivia(
+                TriviaKind::Whitespace,
+                &source[position..span.start],
+            ));
+        }
+
+        // the trivia up to the first line break stays with the previous
+        // token, the rest leads into this one
+        let (trailing, next_leading) = if index == 0 {
+            (Vec::new(), gap)
+        } else {
+            split_at_line_break(gap)
+        };
+        if let Some(previous) = index.checked_sub(1) {
+            let (kind, span) = &spans[previous];
+            tokens.push(GreenToken::new(
+                kind.clone(),
+                source[*span].to_string(),
+                leading,
+                trailing,
+            ));
+        }
+
+        leading = next_leading;
+        end = span.end;
+    }
+
+    let (kind, span) = spans.last().expect("`Eof` was pushed above");
+    tokens.push(GreenToken::new(
+        kind.clone(),
+        source[*span].to_string(),
+        leading,
+        Vec::new(),
+    ));
+
+    tokens
+}
+
+fn trivia(kind: TriviaKind, text: &str) -> Trivia {
+    Trivia {
+        kind,
+        text: text.to_string(),
+    }
+}
+
+fn split_at_line_break(gap: Vec<Trivia>) -> (Vec<Trivia>, Vec<Trivia>) {
+    let mut trailing = Vec::new();
+    let mut leading = Vec::new();
+
+    for piece in gap {
+        if !leading.is_empty() {
+            leading.push(piece);
+            continue;
+        }
+
+        match piece.text.find('\n') {
+            Some(line_break) if piece.kind == TriviaKind::Whitespace => {
+                let (before, after) = piece.text.split_at(line_break);
+                // `\r\n` belongs together
+                let (before, after) = match before.strip_suffix('\r') {
+                    Some(before) => (before, &piece.text[line_break - 1..]),
+                    None => (before, after),
+                };
+                if !before.is_empty() {
+                    trailing.push(trivia(TriviaKind::Whitespace, before));
+                }
+                leading.push(trivia(TriviaKind::Whitespace, after));
+            }
+            _ => trailing.push(piece),
+        }
+    }
+
+    (trailing, leading)
+}
+
+/// Assembles green nodes bottom-up. Nodes are opened, filled with tokens and
+/// child nodes, and closed again; a checkpoint allows opening a node around
+/// children that were already added, as a binary expression around its
+/// left-hand side.
+#[derive(Default)]
+struct Builder {
+    /// The kind of every open node and the index of its first child.
+    open: Vec<(NodeKind, usize)>,
+    children: Vec<GreenElement>,
+}
+
+impl Builder {
+    fn start_node(&mut self, kind: NodeKind) {
+        self.open.push((kind, self.children.len()));
+    }
+
+    fn checkpoint(&self) -> usize {
+        self.children.len()
+    }
+
+    fn start_node_at(&mut self, checkpoint: usize, kind: NodeKind) {
+        self.open.push((kind, checkpoint));
+    }
+
+    fn finish_node(&mut self) {
+        let (kind, first) = self.open.pop().expect("a node should be open");
+        let children = self.children.split_off(first);
+        self.children
+            .push(GreenElement::Node(Rc::new(GreenNode::new(kind, children))));
+    }
+
+    fn token(&mut self, token: GreenToken) {
+        self.children.push(GreenElement::Token(Rc::new(token)));
+    }
+
+    fn finish(mut self) -> GreenNode {
+        assert!(self.open.is_empty(), "every node should be finished");
+        match self.children.pop() {
+            Some(GreenElement::Node(root)) if self.children.is_empty() => {
+                Rc::try_unwrap(root).expect("the root is not shared yet")
+            }
+            _ => panic!("the tree should have a single root"),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<GreenToken>,
+    position: usize,
+    builder: Builder,
+}
+
+impl Parser {
+    /// Above every binary operator, as in `parser::Parser`.
+    const PREFIX_BINDING_POWER: u8 = 8;
+
+    fn source_file(&mut self) {
+        self.builder.start_node(NodeKind::SourceFile);
+        while !self.at(TokenKind::Eof) {
+            if self.at(TokenKind::Fn) {
+                self.function();
+            } else {
+                self.error();
+            }
+        }
+        self.bump();
+        self.builder.finish_node();
+    }
+
+    fn function(&mut self) {
+        self.builder.start_node(NodeKind::Function);
+        self.bump();
+        self.eat(TokenKind::Identifier);
+
+        self.builder.start_node(NodeKind::ParameterList);
+        if self.eat(TokenKind::LParen) {
+            while self.eat(TokenKind::Identifier) && self.eat(TokenKind::Comma) {}
+            self.eat(TokenKind::RParen);
+        }
+        self.builder.finish_node();
+
+        self.block();
+        self.builder.finish_node();
+    }
+
+    fn block(&mut self) {
+        if !self.at(TokenKind::LBrace) {
+            return;
+        }
+
+        self.builder.start_node(NodeKind::Block);
+        self.bump();
+        while !self.at(TokenKind::RBrace) && !self.at(TokenKind::Eof) {
+            if self.at(TokenKind::Fn) {
+                // most likely the closing brace is missing, so leave the
+                // function to the source file
+                break;
+            }
+            self.statement();
+        }
+        self.eat(TokenKind::RBrace);
+        self.builder.finish_node();
+    }
+
+    fn statement(&mut self) {
+        match self.current() {
+            TokenKind::LBrace => self.block(),
+            TokenKind::Let => {
+                self.builder.start_node(NodeKind::LetStatement);
+                self.bump();
+                self.eat(TokenKind::Identifier);
+                self.eat(TokenKind::Equal);
+                self.expression(0);
+                self.eat(TokenKind::Semicolon);
+                self.builder.finish_node();
+            }
+            TokenKind::Return => {
+                self.builder.start_node(NodeKind::ReturnStatement);
+                self.bump();
+                self.expression(0);
+                self.eat(TokenKind::Semicolon);
+                self.builder.finish_node();
+            }
+            TokenKind::If => {
+                self.builder.start_node(NodeKind::IfStatement);
+                self.bump();
+                self.expression(0);
+                self.block();
+                if self.eat(TokenKind::Else) {
+                    self.block();
+                }
+                self.builder.finish_node();
+            }
+            TokenKind::While => {
+                self.builder.start_node(NodeKind::WhileStatement);
+                self.bump();
+                self.expression(0);
+                self.block();
+                self.builder.finish_node();
+            }
+            _ if self.at_expression() => {
+                self.builder.start_node(NodeKind::ExpressionStatement);
+                self.expression(0);
+                self.eat(TokenKind::Semicolon);
+                self.builder.finish_node();
+            }
+            _ => self.error(),
+        }
+    }
+
+    fn at_expression(&self) -> bool {
+        matches!(
+            self.current(),
+            TokenKind::Int
+                | TokenKind::IntOverflow
+                | TokenKind::InvalidInt
+                | TokenKind::True
+                | TokenKind::False
+                | TokenKind::Identifier
+                | TokenKind::LParen
+                | TokenKind::Bang
+                | TokenKind::Minus
+        )
+    }
+
+    /// Parses an expression whose operators bind tighter than `rbp`, if one
+    /// starts here.
+    fn expression(&mut self, rbp: u8) {
+        let checkpoint = self.builder.checkpoint();
+        if !self.nud() {
+            return;
+        }
+
+        loop {
+            let binding_power = Parser::binding_power(self.current());
+            if binding_power <= rbp {
+                break;
+            }
+
+            if self.at(TokenKind::Equal) {
+                // assignment is the only right-associative operator
+                self.builder
+                    .start_node_at(checkpoint, NodeKind::AssignmentExpression);
+                self.bump();
+                self.expression(binding_power - 1);
+            } else {
+                self.builder
+                    .start_node_at(checkpoint, NodeKind::BinaryExpression);
+                self.bump();
+                self.expression(binding_power);
+            }
+            self.builder.finish_node();
+        }
+    }
+
+    /// Parses the start of an expression. Returns `false` without consuming
+    /// anything if no expression starts here.
+    fn nud(&mut self) -> bool {
+        match self.current() {
+            TokenKind::Int
+            | TokenKind::IntOverflow
+            | TokenKind::InvalidInt
+            | TokenKind::True
+            | TokenKind::False => {
+                self.builder.start_node(NodeKind::Literal);
+                self.bump();
+            }
+            TokenKind::Identifier
+                if self
+                    .tokens
+                    .get(self.position + 1)
+                    .is_some_and(|token| *token.kind() == TokenKind::LParen) =>
+            {
+                self.builder.start_node(NodeKind::CallExpression);
+                self.bump();
+                self.builder.start_node(NodeKind::ArgumentList);
+                self.bump();
+                if !self.at(TokenKind::RParen) {
+                    self.expression(0);
+                    while self.eat(TokenKind::Comma) {
+                        self.expression(0);
+                    }
+                }
+                self.eat(TokenKind::RParen);
+                self.builder.finish_node();
+            }
+            TokenKind::Identifier => {
+                self.builder.start_node(NodeKind::Reference);
+                self.bump();
+            }
+            TokenKind::LParen => {
+                self.builder.start_node(NodeKind::ParenExpression);
+                self.bump();
+                self.expression(0);
+                self.eat(TokenKind::RParen);
+            }
+            TokenKind::Bang | TokenKind::Minus => {
+                self.builder.start_node(NodeKind::PrefixExpression);
+                self.bump();
+                self.expression(Parser::PREFIX_BINDING_POWER);
+            }
+            _ => return false,
+        }
+
+        self.builder.finish_node();
+        true
+    }
+
+    /// Wraps the current token in an `Error` node.
+    fn error(&mut self) {
+        self.builder.start_node(NodeKind::Error);
+        self.bump();
+        self.builder.finish_node();
+    }
+
+    fn current(&self) -> TokenKind {
+        self.tokens[self.position].kind().clone()
+    }
+
+    fn at(&self, kind: TokenKind) -> bool {
+        self.current() == kind
+    }
+
+    /// Adds the current token to the open node and moves past it. `Eof` is
+    /// only added once.
+    fn bump(&mut self) {
+        let token = std::mem::replace(
+            &mut self.tokens[self.position],
+            GreenToken::new(TokenKind::Eof, String::new(), Vec::new(), Vec::new()),
+        );
+        self.builder.token(token);
+        if self.position + 1 < self.tokens.len() {
+            self.position += 1;
+        }
+    }
+
+    fn eat(&mut self, kind: TokenKind) -> bool {
+        if self.at(kind) {
+            self.bump();
+            return true;
+        }
+
+        false
+    }
+
+    /// Matches `parser::Parser::binding_power`.
+    fn binding_power(kind: TokenKind) -> u8 {
+        match kind {
+            TokenKind::Equal => 1,
+            TokenKind::OrOr => 2,
+            TokenKind::AndAnd => 3,
+            TokenKind::EqualEqual | TokenKind::BangEqual => 4,
+            TokenKind::Less
+            | TokenKind::LessEqual
+            | TokenKind::Greater
+            | TokenKind::GreaterEqual => 5,
+            TokenKind::Plus | TokenKind::Minus => 6,
+            TokenKind::Asterisk | TokenKind::Slash => 7,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_lossless(source: &str) {
+        assert_eq!(parse(source).to_string(), source, "for {source:?}");
+    }
+
+    #[test]
+    fn reproduces_valid_source() {
+        assert_lossless(include_str!("../../examples/example1.inv"));
+        assert_lossless(include_str!("../../examples/example2.inv"));
+        assert_lossless("fn f(a,b){return a+b*-c;}");
+        assert_lossless("fn f() {\r\n    x = y = 1; // crlf\r\n}\r\n");
+    }
+
+    #[test]
+    fn reproduces_invalid_source() {
+        let cases = [
+            "",
+            "  \n\t ",
+            "// only a comment",
+            "fn",
+            "fn f(",
+            "fn f() { let = ; }",
+            "fn f() { return 1 +; } }",
+            "let x = 1;",
+            "fn f() { if { } else else {} }",
+            "fn f() { g(1,, 2; }",
+            "fn f() { 1 @ 2 & 3; }",
+            "fn f() { 0xZZ + 99999999999999999999; }",
+            "fn f() {\n fn g() {}",
+            "fn f() { /* never closed",
+            "fn f() {} /* nested /* */ */ ä",
+        ];
+
+        for source in cases {
+            assert_lossless(source);
+        }
+    }
+
+    #[test]
+    fn attaches_trivia_to_tokens() {
+        let tree = parse("// leading\nfn f() { // trailing\n  return 1; /* end */ }\n");
+
+        assert_eq!(
+            tree.debug_tree(),
+            r#"SourceFile@11..56
+  Function@11..55
+    Fn@11..13 "fn" leading "// leading\n" trailing " "
+    Identifier@14..15 "f"
+    ParameterList@15..17
+      LParen@15..16 "("
+      RParen@16..17 ")" trailing " "
+    Block@18..55
+      LBrace@18..19 "{" trailing " // trailing"
+      ReturnStatement@34..43
+        Return@34..40 "return" leading "\n  " trailing " "
+        Literal@41..42
+          Int@41..42 "1"
+        Semicolon@42..43 ";" trailing " /* end */ "
+      RBrace@54..55 "}"
+  Eof@56..56 "" leading "\n"
+"#
+        );
+    }
+
+    #[test]
+    fn wraps_unexpected_tokens_in_error_nodes() {
+        let tree = parse("let fn f() { ) }");
+        let kinds = tree.children().map(|node| node.kind()).collect::<Vec<_>>();
+
+        assert_eq!(kinds, [NodeKind::Error, NodeKind::Function]);
+        let body = tree
+            .children()
+            .nth(1)
+            .and_then(|function| function.children().nth(1));
+        assert_eq!(
+            body.map(|body| body.children().map(|node| node.kind()).collect::<Vec<_>>()),
+            Some(vec![NodeKind::Error])
+        );
+    }
+
+    #[test]
+    fn spans_exclude_trivia() {
+        let source = "fn f() {\n    let x = (1 + 2) * 3; // note\n}\n";
+        let tree = parse(source);
+        let tokens = tree.tokens();
+
+        for token in &tokens {
+            assert_eq!(&source[token.span()], token.text());
+        }
+        let full = tokens
+            .iter()
+            .map(|token| &source[token.full_span()])
+            .collect::<String>();
+        assert_eq!(full, source);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::syntax::{GreenElement, GreenNode, GreenToken, NodeKind};
+use crate::token::TokenKind;
+use std::fmt::{self, Write};
+use std::range::Range;
+use std::rc::Rc;
+
+/// A green node together with its parent and its offset in the source.
+#[derive(Debug, Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+#[derive(Debug)]
+struct NodeData {
+    green: Rc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    /// The offset of the node, including the leading trivia of its first
+    /// token.
+    offset: usize,
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: Rc<GreenNode>) -> Self {
+        Self(Rc::new(NodeData {
+            green,
+            parent: None,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn parent(&self) -> Option<&SyntaxNode> {
+        self.0.parent.as_ref()
+    }
+
+    /// The range of the node including the trivia of its first and last
+    /// token.
+    pub fn full_span(&self) -> Range<usize> {
+        Range::from(self.0.offset..self.0.offset + self.0.green.width())
+    }
+
+    /// The range of the node without the trivia around it. Nodes without any
+    /// token, like an empty `Error` node, have an empty span.
+    pub fn span(&self) -> Range<usize> {
+        let tokens = self.tokens();
+        match (tokens.first(), tokens.last()) {
+            (Some(first), Some(last)) => Range::from(first.span().start..last.span().end),
+            _ => Range::from(self.0.offset..self.0.offset),
+        }
+    }
+
+    pub fn children_with_tokens(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut children = Vec::with_capacity(self.0.green.children().len());
+
+        for child in self.0.green.children() {
+            children.push(match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green: Rc::clone(green),
+                    parent: Some(self.clone()),
+                    offset,
+                }))),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: Rc::clone(green),
+                    parent: self.clone(),
+                    offset,
+                }),
+            });
+            offset += child.width();
+        }
+
+        children
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(SyntaxElement::into_node)
+    }
+
+    /// The tokens directly below this node.
+    pub fn child_tokens(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(SyntaxElement::into_token)
+    }
+
+    /// Every token in this subtree, in source order.
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = Vec::new();
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+
+        tokens
+    }
+
+    /// Renders the tree with one line per node and token, for debugging and
+    /// tests. Trivia is shown next to the token that owns it.
+    pub fn debug_tree(&self) -> String {
+        let mut out = String::new();
+        self.write_debug_tree(&mut out, 0);
+
+        out
+    }
+
+    fn write_debug_tree(&self, out: &mut String, depth: usize) {
+        let span = self.span();
+        let _ = writeln!(
+            out,
+            "{:indent$}{:?}@{}..{}",
+            "",
+            self.kind(),
+            span.start,
+            span.end,
+            indent = depth * 2
+        );
+
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => node.write_debug_tree(out, depth + 1),
+                SyntaxElement::Token(token) => {
+                    let span = token.span();
+                    let _ = write!(
+                        out,
+                        "{:indent$}{:?}@{}..{} {:?}",
+                        "",
+                        token.kind(),
+                        span.start,
+                        span.end,
+                        token.text(),
+                        indent = (depth + 1) * 2
+                    );
+                    for (side, trivia) in [
+                        ("leading", token.green.leading()),
+                        ("trailing", token.green.trailing()),
+                    ] {
+                        if !trivia.is_empty() {
+                            let text = trivia
+                                .iter()
+                                .map(|trivia| trivia.text.as_str())
+                                .collect::<String>();
+                            let _ = write!(out, " {side} {text:?}");
+                        }
+                    }
+                    out.push('\n');
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    /// Writes the source text of the node, including all trivia.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.green.fmt(f)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    parent: SyntaxNode,
+    /// The offset of the token, including its leading trivia.
+    offset: usize,
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> &TokenKind {
+        self.green.kind()
+    }
+
+    pub fn green(&self) -> &Rc<GreenToken> {
+        &self.green
+    }
+
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    pub fn parent(&self) -> &SyntaxNode {
+        &self.parent
+    }
+
+    /// The range of the token without its trivia.
+    pub fn span(&self) -> Range<usize> {
+        let start = self.offset + self.green.leading_width();
+
+        Range::from(start..start + self.green.text().len())
+    }
+
+    pub fn full_span(&self) -> Range<usize> {
+        Range::from(self.offset..self.offset + self.green.width())
+    }
+}
+
+impl fmt::Display for SyntaxToken {
+    /// Writes the text of the token, including its trivia.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.green.fmt(f)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+    pub fn into_node(self) -> Option<SyntaxNode> {
+        match self {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        }
+    }
+
+    pub fn into_token(self) -> Option<SyntaxToken> {
+        match self {
+            SyntaxElement::Node(_) => None,
+            SyntaxElement::Token(token) => Some(token),
+        }
+    }
+}
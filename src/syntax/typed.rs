@@ -0,0 +1,390 @@
+//! Typed views of the concrete syntax tree.
+//!
+//! Every type wraps a `SyntaxNode` of one `NodeKind` and offers accessors for
+//! its parts. The tree may be incomplete, so every accessor returns an
+//! `Option` or skips what is missing.
+
+use crate::syntax::{NodeKind, SyntaxElement, SyntaxNode, SyntaxToken};
+use crate::token::TokenKind;
+
+pub trait TypedNode: Sized {
+    /// Wraps `node` if it has the kind of this type.
+    fn cast(node: SyntaxNode) -> Option<Self>;
+
+    fn syntax(&self) -> &SyntaxNode;
+}
+
+macro_rules! typed_nodes {
+    ($($name:ident),* $(,)?) => {
+        $(
+            #[derive(Debug, Clone)]
+            pub struct $name(SyntaxNode);
+
+            impl TypedNode for $name {
+                fn cast(node: SyntaxNode) -> Option<Self> {
+                    (node.kind() == NodeKind::$name).then_some(Self(node))
+                }
+
+                fn syntax(&self) -> &SyntaxNode {
+                    &self.0
+                }
+            }
+        )*
+    };
+}
+
+typed_nodes!(
+    SourceFile,
+    Function,
+    ParameterList,
+    Block,
+    LetStatement,
+    ExpressionStatement,
+    ReturnStatement,
+    IfStatement,
+    WhileStatement,
+    Literal,
+    Reference,
+    ParenExpression,
+    PrefixExpression,
+    BinaryExpression,
+    AssignmentExpression,
+    CallExpression,
+    ArgumentList,
+);
+
+fn child<N: TypedNode>(node: &SyntaxNode) -> Option<N> {
+    node.children().find_map(N::cast)
+}
+
+fn children<N: TypedNode>(node: &SyntaxNode) -> impl Iterator<Item = N> {
+    node.children().filter_map(N::cast)
+}
+
+fn token(node: &SyntaxNode, kind: TokenKind) -> Option<SyntaxToken> {
+    node.child_tokens().find(|token| *token.kind() == kind)
+}
+
+impl SourceFile {
+    pub fn functions(&self) -> impl Iterator<Item = Function> {
+        children(&self.0)
+    }
+}
+
+impl Function {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenKind::Identifier)
+    }
+
+    pub fn parameters(&self) -> Option<ParameterList> {
+        child(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+impl ParameterList {
+    pub fn names(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.0
+            .child_tokens()
+            .filter(|token| *token.kind() == TokenKind::Identifier)
+    }
+}
+
+impl Block {
+    pub fn statements(&self) -> impl Iterator<Item = Statement> {
+        children(&self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Statement {
+    Let(LetStatement),
+    Expression(ExpressionStatement),
+    Return(ReturnStatement),
+    If(IfStatement),
+    While(WhileStatement),
+    Block(Block),
+}
+
+impl TypedNode for Statement {
+    fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            NodeKind::LetStatement => Some(Statement::Let(LetStatement(node))),
+            NodeKind::ExpressionStatement => Some(Statement::Expression(ExpressionStatement(node))),
+            NodeKind::ReturnStatement => Some(Statement::Return(ReturnStatement(node))),
+            NodeKind::IfStatement => Some(Statement::If(IfStatement(node))),
+            NodeKind::WhileStatement => Some(Statement::While(WhileStatement(node))),
+            NodeKind::Block => Some(Statement::Block(Block(node))),
+            _ => None,
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Statement::Let(statement) => statement.syntax(),
+            Statement::Expression(statement) => statement.syntax(),
+            Statement::Return(statement) => statement.syntax(),
+            Statement::If(statement) => statement.syntax(),
+            Statement::While(statement) => statement.syntax(),
+            Statement::Block(block) => block.syntax(),
+        }
+    }
+}
+
+impl LetStatement {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenKind::Identifier)
+    }
+
+    pub fn value(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl ExpressionStatement {
+    pub fn expression(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl ReturnStatement {
+    pub fn value(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl IfStatement {
+    pub fn condition(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+
+    pub fn then_block(&self) -> Option<Block> {
+        self.0
+            .children_with_tokens()
+            .into_iter()
+            .take_while(|child| !is_else(child))
+            .find_map(|child| child.into_node().and_then(Block::cast))
+    }
+
+    /// The block after `else`, if there is one.
+    pub fn else_block(&self) -> Option<Block> {
+        self.0
+            .children_with_tokens()
+            .into_iter()
+            .skip_while(|child| !is_else(child))
+            .find_map(|child| child.into_node().and_then(Block::cast))
+    }
+}
+
+fn is_else(element: &SyntaxElement) -> bool {
+    matches!(element, SyntaxElement::Token(token) if *token.kind() == TokenKind::Else)
+}
+
+impl WhileStatement {
+    pub fn condition(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Expression {
+    Literal(Literal),
+    Reference(Reference),
+    Paren(ParenExpression),
+    Prefix(PrefixExpression),
+    Binary(BinaryExpression),
+    Assignment(AssignmentExpression),
+    Call(CallExpression),
+}
+
+impl TypedNode for Expression {
+    fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            NodeKind::Literal => Some(Expression::Literal(Literal(node))),
+            NodeKind::Reference => Some(Expression::Reference(Reference(node))),
+            NodeKind::ParenExpression => Some(Expression::Paren(ParenExpression(node))),
+            NodeKind::PrefixExpression => Some(Expression::Prefix(PrefixExpression(node))),
+            NodeKind::BinaryExpression => Some(Expression::Binary(BinaryExpression(node))),
+            NodeKind::AssignmentExpression => {
+                Some(Expression::Assignment(AssignmentExpression(node)))
+            }
+            NodeKind::CallExpression => Some(Expression::Call(CallExpression(node))),
+            _ => None,
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Expression::Literal(expression) => expression.syntax(),
+            Expression::Reference(expression) => expression.syntax(),
+            Expression::Paren(expression) => expression.syntax(),
+            Expression::Prefix(expression) => expression.syntax(),
+            Expression::Binary(expression) => expression.syntax(),
+            Expression::Assignment(expression) => expression.syntax(),
+            Expression::Call(expression) => expression.syntax(),
+        }
+    }
+}
+
+impl Literal {
+    pub fn token(&self) -> Option<SyntaxToken> {
+        self.0.child_tokens().next()
+    }
+}
+
+impl Reference {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenKind::Identifier)
+    }
+}
+
+impl ParenExpression {
+    pub fn expression(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl PrefixExpression {
+    /// The `!` or `-` token.
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        self.0.child_tokens().next()
+    }
+
+    pub fn operand(&self) -> Option<Expression> {
+        child(&self.0)
+    }
+}
+
+impl BinaryExpression {
+    pub fn lhs(&self) -> Option<Expression> {
+        children(&self.0).next()
+    }
+
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        self.0.child_tokens().next()
+    }
+
+    pub fn rhs(&self) -> Option<Expression> {
+        children(&self.0).nth(1)
+    }
+}
+
+impl AssignmentExpression {
+    pub fn target(&self) -> Option<Expression> {
+        children(&self.0).next()
+    }
+
+    pub fn value(&self) -> Option<Expression> {
+        children(&self.0).nth(1)
+    }
+}
+
+impl CallExpression {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenKind::Identifier)
+    }
+
+    pub fn arguments(&self) -> Option<ArgumentList> {
+        child(&self.0)
+    }
+}
+
+impl ArgumentList {
+    pub fn arguments(&self) -> impl Iterator<Item = Expression> {
+        children(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::parse;
+
+    fn source_file(source: &str) -> SourceFile {
+        SourceFile::cast(parse(source)).expect("the root is a source file")
+    }
+
+    #[test]
+    fn navigates_functions_and_statements() {
+        let file =
+            source_file("fn f(a, b) { let x = a; if x { return b; } else { g(); } }\nfn main() {}");
+
+        let names = file
+            .functions()
+            .filter_map(|function| function.name())
+            .map(|name| name.text().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["f", "main"]);
+
+        let f = file.functions().next().unwrap();
+        let parameters = f
+            .parameters()
+            .unwrap()
+            .names()
+            .map(|name| name.text().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(parameters, ["a", "b"]);
+
+        let statements = f.body().unwrap().statements().collect::<Vec<_>>();
+        let [Statement::Let(binding), Statement::If(branch)] = statements.as_slice() else {
+            panic!("unexpected statements {statements:?}");
+        };
+        assert_eq!(binding.name().unwrap().text(), "x");
+        assert_eq!(binding.value().unwrap().syntax().to_string(), "a");
+        assert_eq!(branch.then_block().unwrap().statements().count(), 1);
+        assert_eq!(
+            branch.else_block().unwrap().syntax().to_string(),
+            "{ g(); } "
+        );
+    }
+
+    #[test]
+    fn navigates_expressions() {
+        let file = source_file("fn f() { x = -(1 + y) * g(2, z); }");
+        let Some(Statement::Expression(statement)) = file
+            .functions()
+            .next()
+            .and_then(|function| function.body())
+            .and_then(|body| body.statements().next())
+        else {
+            panic!("expected an expression statement");
+        };
+
+        let Some(Expression::Assignment(assignment)) = statement.expression() else {
+            panic!("expected an assignment");
+        };
+        let Some(Expression::Binary(product)) = assignment.value() else {
+            panic!("expected a binary expression");
+        };
+        assert_eq!(product.operator().unwrap().text(), "*");
+
+        let Some(Expression::Prefix(negation)) = product.lhs() else {
+            panic!("expected a prefix expression");
+        };
+        assert_eq!(negation.operator().unwrap().text(), "-");
+        assert!(matches!(negation.operand(), Some(Expression::Paren(_))));
+
+        let Some(Expression::Call(call)) = product.rhs() else {
+            panic!("expected a call");
+        };
+        assert_eq!(call.name().unwrap().text(), "g");
+        assert_eq!(call.arguments().unwrap().arguments().count(), 2);
+    }
+
+    #[test]
+    fn tolerates_missing_parts() {
+        let file = source_file("fn (");
+        let function = file.functions().next().unwrap();
+
+        assert!(function.name().is_none());
+        assert!(function.body().is_none());
+        assert_eq!(function.parameters().unwrap().names().count(), 0);
+    }
+}
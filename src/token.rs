@@ -1,5 +1,7 @@
+use std::fmt;
+
 #[derive(Debug, Clone)]
-pub(crate) enum Token {
+pub enum Token {
     LParen,
     RParen,
     LBrace,
@@ -23,7 +25,11 @@ pub(crate) enum Token {
     OrOr,
 
     Identifier(String),
-    Int(i64),
+    Int(u64),
+    /// An integer literal too large to be represented, with its source text.
+    IntOverflow(String),
+    /// An integer literal that is not well-formed, with its source text.
+    InvalidInt(String),
 
     Fn,
     Let,
@@ -35,10 +41,12 @@ pub(crate) enum Token {
     False,
 
     UnexpectedCharacter(char),
+    /// A `/*` comment that is still open at the end of the input.
+    UnterminatedComment,
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) enum TokenKind {
+pub enum TokenKind {
     LParen,
     RParen,
     LBrace,
@@ -63,6 +71,8 @@ pub(crate) enum TokenKind {
 
     Identifier,
     Int,
+    IntOverflow,
+    InvalidInt,
 
     Fn,
     Let,
@@ -74,6 +84,10 @@ pub(crate) enum TokenKind {
     False,
 
     UnexpectedCharacter,
+    UnterminatedComment,
+    /// The end of the input. The lexer never emits it, but the concrete
+    /// syntax tree ends with it to hold the trivia at the end of the file.
+    Eof,
 }
 
 impl Token {
@@ -101,6 +115,8 @@ impl Token {
             Token::OrOr => TokenKind::OrOr,
             Token::Identifier(_) => TokenKind::Identifier,
             Token::Int(_) => TokenKind::Int,
+            Token::IntOverflow(_) => TokenKind::IntOverflow,
+            Token::InvalidInt(_) => TokenKind::InvalidInt,
             Token::Fn => TokenKind::Fn,
             Token::Let => TokenKind::Let,
             Token::If => TokenKind::If,
@@ -110,6 +126,7 @@ impl Token {
             Token::True => TokenKind::True,
             Token::False => TokenKind::False,
             Token::UnexpectedCharacter(_) => TokenKind::UnexpectedCharacter,
+            Token::UnterminatedComment => TokenKind::UnterminatedComment,
         }
     }
 
@@ -120,3 +137,59 @@ impl Token {
         }
     }
 }
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Identifier(identifier) => write!(f, "identifier `{identifier}`"),
+            Token::Int(value) => write!(f, "integer literal `{value}`"),
+            Token::IntOverflow(literal) | Token::InvalidInt(literal) => {
+                write!(f, "integer literal `{literal}`")
+            }
+            Token::UnexpectedCharacter(c) => write!(f, "character `{c}`"),
+            _ => write!(f, "{}", self.kind()),
+        }
+    }
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            TokenKind::LParen => "`(`",
+            TokenKind::RParen => "`)`",
+            TokenKind::LBrace => "`{`",
+            TokenKind::RBrace => "`}`",
+            TokenKind::Comma => "`,`",
+            TokenKind::Semicolon => "`;`",
+            TokenKind::Plus => "`+`",
+            TokenKind::Minus => "`-`",
+            TokenKind::Asterisk => "`*`",
+            TokenKind::Slash => "`/`",
+            TokenKind::Bang => "`!`",
+            TokenKind::Equal => "`=`",
+            TokenKind::EqualEqual => "`==`",
+            TokenKind::BangEqual => "`!=`",
+            TokenKind::Less => "`<`",
+            TokenKind::LessEqual => "`<=`",
+            TokenKind::Greater => "`>`",
+            TokenKind::GreaterEqual => "`>=`",
+            TokenKind::AndAnd => "`&&`",
+            TokenKind::OrOr => "`||`",
+            TokenKind::Identifier => "identifier",
+            TokenKind::Int | TokenKind::IntOverflow | TokenKind::InvalidInt => "integer literal",
+            TokenKind::Fn => "`fn`",
+            TokenKind::Let => "`let`",
+            TokenKind::If => "`if`",
+            TokenKind::Else => "`else`",
+            TokenKind::While => "`while`",
+            TokenKind::Return => "`return`",
+            TokenKind::True => "`true`",
+            TokenKind::False => "`false`",
+            TokenKind::UnexpectedCharacter => "unexpected character",
+            TokenKind::UnterminatedComment => "unterminated block comment",
+            TokenKind::Eof => "end of input",
+        };
+
+        f.write_str(description)
+    }
+}
@@ -0,0 +1,477 @@
+//! A simple type inference, used by the REPL's `:type` command.
+//!
+//! Every value is an `int`, a `bool` or `()`. Operators fix the types of
+//! their operands and their result, so most types follow from the operators
+//! alone. Parameters take the types their uses in the function body demand,
+//! and functions return the type of their `return` statements, or `()` if
+//! they have none. Whatever nothing constrains stays unknown.
+
+use crate::ast::{BinaryOperator, Expression, Item, Operation, Statement, UnaryOperator};
+use crate::check::Builtin;
+use crate::interpret::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::range::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Int,
+    Bool,
+    Unit,
+}
+
+impl Type {
+    pub fn of(value: Value) -> Type {
+        match value {
+            Value::Integer(_) => Type::Int,
+            Value::Boolean(_) => Type::Bool,
+            Value::Unit => Type::Unit,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Type::Int => "int",
+            Type::Bool => "bool",
+            Type::Unit => "()",
+        })
+    }
+}
+
+/// Writes an unknown type as `?`.
+struct Known(Option<Type>);
+
+impl fmt::Display for Known {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(ty) => write!(f, "{ty}"),
+            None => f.write_str("?"),
+        }
+    }
+}
+
+/// The inferred types of a function. `None` stands for a type nothing in the
+/// function constrains.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub parameters: Vec<Option<Type>>,
+    pub result: Option<Type>,
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("fn(")?;
+        for (index, parameter) in self.parameters.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", Known(*parameter))?;
+        }
+        write!(f, ") -> {}", Known(self.result))
+    }
+}
+
+/// An expression whose type differs from the one its context requires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeError {
+    pub expected: Type,
+    pub found: Type,
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "mismatched types: expected `{}`, found `{}`",
+            self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+/// The variables and functions that exist outside the code being inferred.
+pub trait Environment {
+    fn variable(&self, name: &str) -> Option<Type>;
+    fn function(&self, name: &str) -> Option<&Item>;
+}
+
+/// Infers the type of the value each statement produces: the value of an
+/// expression statement, a `let` or a `return`, and `()` for the rest.
+/// Variables bound by earlier statements are visible to later ones.
+pub fn infer_statements<'s>(
+    environment: &impl Environment,
+    statements: impl IntoIterator<Item = &'s Statement>,
+) -> Result<Vec<Option<Type>>, TypeError> {
+    let mut inference = Inference::new(environment);
+
+    statements
+        .into_iter()
+        .map(|statement| {
+            inference.statement(statement)?;
+
+            Ok(match statement {
+                Statement::Binding { bind_to, .. } => inference.variable(bind_to),
+                Statement::Expression { .. } | Statement::Return { .. } => inference.last,
+                _ => Some(Type::Unit),
+            })
+        })
+        .collect()
+}
+
+pub fn infer_function(environment: &impl Environment, item: &Item) -> Result<Signature, TypeError> {
+    Inference::new(environment).function(item)
+}
+
+struct Inference<'e, E> {
+    environment: &'e E,
+    /// The types of the local variables, innermost block last.
+    scopes: Vec<HashMap<String, Option<Type>>>,
+    /// The type of the last expression statement or `return` value.
+    last: Option<Type>,
+    /// The type of the `return`s seen so far in the current function, and
+    /// whether there were any.
+    result: (Option<Type>, bool),
+    /// The functions whose signatures are being inferred. A recursive call
+    /// to one of them has an unknown type.
+    inferring: Vec<String>,
+}
+
+impl<'e, E: Environment> Inference<'e, E> {
+    fn new(environment: &'e E) -> Self {
+        Self {
+            environment,
+            scopes: vec![HashMap::new()],
+            last: None,
+            result: (None, false),
+            inferring: Vec::new(),
+        }
+    }
+
+    fn function(&mut self, item: &Item) -> Result<Signature, TypeError> {
+        let Item::Function {
+            name,
+            arguments,
+            body,
+            ..
+        } = item;
+
+        let parameters = arguments.iter().map(|argument| (argument.clone(), None));
+        let scopes = std::mem::replace(&mut self.scopes, vec![parameters.collect()]);
+        let result = std::mem::take(&mut self.result);
+        self.inferring.push(name.clone());
+
+        let inferred = self.statement(body);
+
+        self.inferring.pop();
+        let frame = std::mem::replace(&mut self.scopes, scopes);
+        let (returned, any_return) = std::mem::replace(&mut self.result, result);
+        inferred?;
+
+        Ok(Signature {
+            parameters: arguments
+                .iter()
+                .map(|argument| frame[0][argument])
+                .collect(),
+            result: if any_return {
+                returned
+            } else {
+                Some(Type::Unit)
+            },
+        })
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), TypeError> {
+        match statement {
+            Statement::Block { body, .. } => {
+                self.scopes.push(HashMap::new());
+                let inferred = body
+                    .iter()
+                    .try_for_each(|statement| self.statement(statement));
+                self.scopes.pop();
+
+                inferred
+            }
+            Statement::Binding { bind_to, value, .. } => {
+                let ty = self.expression(value)?;
+                self.scopes
+                    .last_mut()
+                    .expect("there is always a scope")
+                    .insert(bind_to.clone(), ty);
+
+                Ok(())
+            }
+            Statement::Expression { expression, .. } => {
+                self.last = self.expression(expression)?;
+
+                Ok(())
+            }
+            Statement::Return { value, .. } => {
+                let ty = self.expression(value)?;
+                if let (Some(expected), Some(found)) = (self.result.0, ty)
+                    && expected != found
+                {
+                    return Err(TypeError {
+                        expected,
+                        found,
+                        span: value.span(),
+                    });
+                }
+                self.result = (self.result.0.or(ty), true);
+                self.last = ty;
+
+                Ok(())
+            }
+            Statement::Branch {
+                condition,
+                then,
+                otherwise,
+                ..
+            } => {
+                self.expect(condition, Type::Bool)?;
+                self.statement(then)?;
+                match otherwise {
+                    Some(otherwise) => self.statement(otherwise),
+                    None => Ok(()),
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.expect(condition, Type::Bool)?;
+                self.statement(body)
+            }
+            Statement::Error { .. } => Ok(()),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Result<Option<Type>, TypeError> {
+        match expression {
+            Expression::Integer { .. } => Ok(Some(Type::Int)),
+            Expression::Boolean { .. } => Ok(Some(Type::Bool)),
+            Expression::Reference { name, .. } => Ok(self.variable(name)),
+            Expression::Assignment {
+                assign_to, value, ..
+            } => match self.variable(assign_to) {
+                Some(expected) => {
+                    self.expect(value, expected)?;
+                    Ok(Some(expected))
+                }
+                None => {
+                    let ty = self.expression(value)?;
+                    self.constrain(assign_to, ty);
+                    Ok(ty)
+                }
+            },
+            Expression::Operation {
+                operation: Operation::Unary(operation),
+                ..
+            } => {
+                let ty = match operation.operator() {
+                    UnaryOperator::Not => Type::Bool,
+                    UnaryOperator::Negate => Type::Int,
+                };
+                self.expect(operation.operand(), ty)?;
+
+                Ok(Some(ty))
+            }
+            Expression::Operation {
+                operation: Operation::Binary(operation),
+                ..
+            } => {
+                let (lhs, rhs) = operation.operands();
+                let (operand, result) = match operation.operator() {
+                    BinaryOperator::Equal | BinaryOperator::NotEqual => {
+                        self.equality(lhs, rhs)?;
+                        return Ok(Some(Type::Bool));
+                    }
+                    BinaryOperator::And | BinaryOperator::Or => (Type::Bool, Type::Bool),
+                    BinaryOperator::Add
+                    | BinaryOperator::Sub
+                    | BinaryOperator::Mul
+                    | BinaryOperator::Div => (Type::Int, Type::Int),
+                    BinaryOperator::Less
+                    | BinaryOperator::LessEqual
+                    | BinaryOperator::Greater
+                    | BinaryOperator::GreaterEqual => (Type::Int, Type::Bool),
+                };
+                self.expect(lhs, operand)?;
+                self.expect(rhs, operand)?;
+
+                Ok(Some(result))
+            }
+            Expression::Call {
+                name, arguments, ..
+            } => self.call(name, arguments),
+            Expression::Error { .. } => Ok(None),
+        }
+    }
+
+    /// Both sides of `==` and `!=` must have the same type.
+    fn equality(&mut self, lhs: &Expression, rhs: &Expression) -> Result<(), TypeError> {
+        match self.expression(lhs)? {
+            Some(ty) => self.expect(rhs, ty),
+            None => {
+                if let (Some(ty), Expression::Reference { name, .. }) = (self.expression(rhs)?, lhs)
+                {
+                    self.constrain(name, Some(ty));
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    fn call(&mut self, name: &str, arguments: &[Expression]) -> Result<Option<Type>, TypeError> {
+        // the body of a function the code calls is not part of the code, so
+        // its errors only make its signature unknown
+        let signature = match self.environment.function(name) {
+            Some(item) if !self.inferring.iter().any(|inferring| inferring == name) => {
+                let mut inference = Inference::new(self.environment);
+                inference.inferring = self.inferring.clone();
+                inference.function(item).ok()
+            }
+            _ => None,
+        };
+
+        let parameters = signature
+            .as_ref()
+            .map(|signature| signature.parameters.as_slice())
+            .unwrap_or_default();
+        for (index, argument) in arguments.iter().enumerate() {
+            match parameters.get(index).copied().flatten() {
+                Some(ty) => self.expect(argument, ty)?,
+                None => {
+                    self.expression(argument)?;
+                }
+            }
+        }
+
+        Ok(match (signature, Builtin::lookup(name)) {
+            (Some(signature), _) => signature.result,
+            (None, Some(Builtin::Print)) => Some(Type::Unit),
+            (None, None) => None,
+        })
+    }
+
+    /// Checks that `expression` has type `expected`. A variable of unknown
+    /// type takes on the expected one.
+    fn expect(&mut self, expression: &Expression, expected: Type) -> Result<(), TypeError> {
+        match self.expression(expression)? {
+            Some(found) if found != expected => Err(TypeError {
+                expected,
+                found,
+                span: expression.span(),
+            }),
+            Some(_) => Ok(()),
+            None => {
+                if let Expression::Reference { name, .. } = expression {
+                    self.constrain(name, Some(expected));
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    fn variable(&self, name: &str) -> Option<Type> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            Some(ty) => *ty,
+            None => self.environment.variable(name),
+        }
+    }
+
+    /// Records the type of a local variable whose type was unknown so far.
+    fn constrain(&mut self, name: &str, ty: Option<Type>) {
+        if let Some(slot) = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+            && slot.is_none()
+        {
+            *slot = ty;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Program;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    struct Functions(Program);
+
+    impl Environment for Functions {
+        fn variable(&self, _: &str) -> Option<Type> {
+            None
+        }
+
+        fn function(&self, name: &str) -> Option<&Item> {
+            self.0.items().iter().find(|item| item.name() == name)
+        }
+    }
+
+    fn signatures(source: &str) -> Vec<String> {
+        let (program, errors) = Parser::new(Lexer::new(source)).parse();
+        assert!(errors.is_empty(), "`{source}` failed to parse: {errors:?}");
+        let environment = Functions(program);
+
+        environment
+            .0
+            .items()
+            .iter()
+            .map(|item| match infer_function(&environment, item) {
+                Ok(signature) => format!("{}: {signature}", item.name()),
+                Err(error) => format!("{}: {}: {error}", item.name(), &source[error.span]),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn infers_signatures_from_operators_and_returns() {
+        assert_eq!(
+            signatures(
+                "fn add(a, b) { return a + b; } \
+                 fn not(x) { if x { return false; } return true; } \
+                 fn id(x) { return x; } \
+                 fn nothing() { print(1); } \
+                 fn twice(n) { return add(n, n) * 2; } \
+                 fn fact(n) { if n == 0 { return 1; } return n * fact(n - 1); } \
+                 fn same(a, b) { let c = a; c = 1; return a == b && c == 1; }"
+            ),
+            [
+                "add: fn(int, int) -> int",
+                "not: fn(bool) -> bool",
+                "id: fn(?) -> ?",
+                "nothing: fn() -> ()",
+                "twice: fn(int) -> int",
+                "fact: fn(int) -> int",
+                "same: fn(?, ?) -> bool",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_mismatched_types() {
+        assert_eq!(
+            signatures(
+                "fn f(a) { return a + true; } \
+                 fn g(a) { if a { return 1; } return false; } \
+                 fn h(a) { let b = a < 1; return !a; } \
+                 fn k() { return f(1) == true; }"
+            ),
+            [
+                "f: true: mismatched types: expected `int`, found `bool`",
+                "g: false: mismatched types: expected `int`, found `bool`",
+                "h: a: mismatched types: expected `bool`, found `int`",
+                "k: fn() -> bool",
+            ]
+        );
+    }
+}
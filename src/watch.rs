@@ -0,0 +1,175 @@
+//! Polling for file changes, behind `--watch`.
+//!
+//! Only the standard library is used, so watching works wherever the files
+//! can be read, including containers and network file systems that platform
+//! notification APIs do not cover.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often `Watcher::wait` looks at the files.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What a file looked like when it was last polled. Comparing the length as
+/// well catches edits within the resolution of the modification time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl Stamp {
+    /// `None` for a file that cannot be read, so that it counts as changed
+    /// once it appears.
+    fn of(path: &Path) -> Option<Stamp> {
+        let metadata = fs::metadata(path).ok()?;
+
+        Some(Stamp {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+pub struct Watcher {
+    /// Lists the files to watch, again on every poll, so that files added
+    /// to a project are picked up.
+    list: Box<dyn FnMut() -> Vec<PathBuf>>,
+    files: Vec<(PathBuf, Option<Stamp>)>,
+}
+
+impl Watcher {
+    /// Starts watching `paths`, taking their current state as unchanged.
+    pub fn new(paths: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        let paths: Vec<PathBuf> = paths.into_iter().map(Into::into).collect();
+
+        Self::listing(move || paths.clone())
+    }
+
+    /// Starts watching the files `list` returns, taking their current state
+    /// as unchanged.
+    pub fn listing(list: impl FnMut() -> Vec<PathBuf> + 'static) -> Self {
+        let mut watcher = Self {
+            list: Box::new(list),
+            files: Vec::new(),
+        };
+        watcher.poll();
+
+        watcher
+    }
+
+    /// Looks at every file once and returns whether any of them was
+    /// modified, created or removed since the last poll, or joined or left
+    /// the list.
+    pub fn poll(&mut self) -> bool {
+        let paths = (self.list)();
+        let mut changed = paths.len() != self.files.len();
+        let files = paths
+            .into_iter()
+            .map(|path| {
+                let stamp = Stamp::of(&path);
+                let previous = self.files.iter().find(|(known, _)| *known == path);
+                if previous.is_none_or(|&(_, known)| known != stamp) {
+                    changed = true;
+                }
+
+                (path, stamp)
+            })
+            .collect();
+        self.files = files;
+
+        changed
+    }
+
+    /// Blocks until a file changes. Editors often save in several steps, so
+    /// it waits for the files to stay unchanged for one more interval.
+    pub fn wait(&mut self) {
+        self.wait_with(|| thread::sleep(POLL_INTERVAL));
+    }
+
+    /// `wait`, with `sleep` between two polls.
+    fn wait_with(&mut self, mut sleep: impl FnMut()) {
+        while !self.poll() {
+            sleep();
+        }
+        loop {
+            sleep();
+            if !self.poll() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notices_edits_creation_and_removal() {
+        let path = std::env::temp_dir().join(format!("invariant-watch-{}.inv", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut watcher = Watcher::new([&path]);
+        assert!(!watcher.poll());
+
+        fs::write(&path, "fn main() {}").unwrap();
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+
+        fs::write(&path, "fn main() { print(1); }").unwrap();
+        assert!(watcher.poll());
+
+        fs::remove_file(&path).unwrap();
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+    }
+
+    #[test]
+    fn notices_files_joining_and_leaving_the_list() {
+        let path = std::env::temp_dir().join(format!("invariant-list-{}.inv", std::process::id()));
+        fs::write(&path, "").unwrap();
+
+        let listed = std::rc::Rc::new(std::cell::Cell::new(false));
+        let mut watcher = Watcher::listing({
+            let (listed, path) = (listed.clone(), path.clone());
+            move || listed.get().then(|| path.clone()).into_iter().collect()
+        });
+        assert!(!watcher.poll());
+
+        listed.set(true);
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+
+        listed.set(false);
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn waits_for_a_save_in_several_steps_to_settle() {
+        let path =
+            std::env::temp_dir().join(format!("invariant-settle-{}.inv", std::process::id()));
+        fs::write(&path, "").unwrap();
+        let mut watcher = Watcher::new([&path]);
+
+        // each step changes the length, which does not depend on the
+        // resolution of the modification time
+        let mut steps = ["fn", "fn main", "fn main() {}"].into_iter();
+        let mut sleeps = 0;
+        watcher.wait_with(|| {
+            sleeps += 1;
+            if let Some(contents) = steps.next() {
+                fs::write(&path, contents).unwrap();
+            }
+        });
+
+        // one sleep before each step, and one to see the last step settle
+        assert_eq!(sleeps, 4);
+        assert!(!watcher.poll());
+        fs::remove_file(&path).unwrap();
+    }
+}
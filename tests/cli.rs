@@ -0,0 +1,195 @@
+//! Runs the `invariant` binary and checks its output and exit status.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::{env, fs};
+
+/// Runs `invariant` with `arguments`, feeding `input` to its standard input.
+fn invariant(arguments: &[&str], input: &str) -> Output {
+    invariant_in(&env::temp_dir(), arguments, input)
+}
+
+/// Runs `invariant` like `invariant`, but in the directory `directory`.
+fn invariant_in(directory: &Path, arguments: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_invariant"))
+        .current_dir(directory)
+        .args(arguments)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the binary should start");
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.as_bytes())
+        .expect("the binary should read its input");
+
+    child.wait_with_output().expect("the binary should finish")
+}
+
+fn status(output: &Output) -> Option<i32> {
+    output.status.code()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn runs_programs_from_stdin() {
+    let output = invariant(&["run", "-"], "fn main() { print(1 + 2); }");
+
+    assert_eq!(status(&output), Some(0));
+    assert_eq!(stdout(&output), "3\n");
+}
+
+#[test]
+fn exit_status_tells_failures_apart() {
+    let parse_error = invariant(&["run", "-"], "fn main( {");
+    assert_eq!(status(&parse_error), Some(1));
+    assert!(stderr(&parse_error).contains("<stdin>:1:"));
+
+    let check_error = invariant(&["check", "-"], "fn main() { x; }");
+    assert_eq!(status(&check_error), Some(1));
+    assert!(stderr(&check_error).contains("cannot find variable `x`"));
+
+    let runtime_error = invariant(&["run", "-"], "fn main() { let x = 0; print(1 / x); }");
+    assert_eq!(status(&runtime_error), Some(3));
+    assert!(stderr(&runtime_error).contains("division by zero"));
+
+    assert_eq!(status(&invariant(&["frobnicate"], "")), Some(2));
+    assert_eq!(
+        status(&invariant(&["emit", "--format=ir", "-"], "")),
+        Some(2)
+    );
+    assert_eq!(status(&invariant(&["check", "missing.inv"], "")), Some(2));
+    assert_eq!(status(&invariant(&["run", "--watch", "-"], "")), Some(2));
+}
+
+#[test]
+fn stops_quietly_when_the_reader_goes_away() {
+    let source = "fn main() { print(1 + 2); }\n".repeat(2_000);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_invariant"))
+        .args(["emit", "--format=tokens-json", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the binary should start");
+    // close the read end before the output could fit into the pipe
+    drop(child.stdout.take());
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(source.as_bytes())
+        .expect("the binary should read its input");
+
+    let output = child.wait_with_output().expect("the binary should finish");
+    assert_eq!(status(&output), Some(0), "{}", stderr(&output));
+}
+
+#[test]
+fn prints_usage_and_version() {
+    let help = invariant(&["--help"], "");
+    assert_eq!(status(&help), Some(0));
+    assert!(stdout(&help).starts_with("usage: invariant <command>"));
+
+    let version = invariant(&["--version"], "");
+    assert_eq!(
+        stdout(&version),
+        format!("invariant {}\n", env!("CARGO_PKG_VERSION"))
+    );
+
+    assert_eq!(status(&invariant(&[], "")), Some(2));
+}
+
+#[test]
+fn runs_and_checks_json_trees() {
+    let tree = stdout(&invariant(
+        &["emit", "--format=ast-json", "-"],
+        "fn main() { print(6 * 7); }",
+    ));
+
+    let run = invariant(&["run", "--input=ast-json", "-"], &tree);
+    assert_eq!((status(&run), stdout(&run)), (Some(0), "42\n".to_string()));
+
+    let unresolved = stdout(&invariant(
+        &["emit", "--format=ast-json", "-"],
+        "fn main() { x; }",
+    ));
+    let check = invariant(&["check", "--input=ast-json", "-"], &unresolved);
+    assert_eq!(status(&check), Some(1));
+    assert!(stderr(&check).contains("cannot find variable `x`"));
+
+    let broken = tree.replacen("\"Integer\"", "\"Error\"", 1);
+    let check = invariant(&["check", "--input=ast-json", "-"], &broken);
+    assert_eq!(status(&check), Some(1));
+    assert!(stderr(&check).contains("the tree has an error node at bytes 18..19"));
+    assert_eq!(
+        status(&invariant(&["run", "--input=ast-json", "-"], &broken)),
+        Some(1)
+    );
+
+    assert_eq!(
+        status(&invariant(&["check", "--input=ast-json", "-"], "{}")),
+        Some(1)
+    );
+    assert_eq!(
+        status(&invariant(&["check", "--input=ir", "-"], "")),
+        Some(2)
+    );
+}
+
+#[test]
+fn formats_stdin_to_stdout() {
+    let output = invariant(&["fmt", "-"], "fn main(){return 1;}");
+
+    assert_eq!(status(&output), Some(0));
+    assert_eq!(stdout(&output), "fn main() {\n    return 1;\n}\n");
+}
+
+#[test]
+fn repl_continues_unbalanced_input() {
+    let output = invariant(
+        &["repl"],
+        "fn f(n) {\nreturn n + 1;\n}\nf(41)\n:quit\nf(0)\n",
+    );
+
+    assert_eq!(status(&output), Some(0));
+    assert!(stdout(&output).ends_with(">> .. .. >> 42\n>> "));
+}
+
+#[test]
+fn creates_builds_and_runs_projects() {
+    let directory = env::temp_dir().join(format!("invariant-cli-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+
+    assert_eq!(
+        status(&invariant_in(&directory, &["new", "demo"], "")),
+        Some(0)
+    );
+    let project = directory.join("demo");
+
+    let build = invariant_in(&project, &["build"], "");
+    assert_eq!(status(&build), Some(0));
+    assert!(stdout(&build).contains("1 built, 0 up to date"));
+    assert!(stdout(&invariant_in(&project, &["build"], "")).contains("0 built, 1 up to date"));
+
+    let run = invariant_in(&project.join("src"), &["run"], "");
+    assert_eq!((status(&run), stdout(&run)), (Some(0), "42\n".to_string()));
+
+    fs::write(project.join("src/broken.inv"), "fn f( {").unwrap();
+    assert_eq!(status(&invariant_in(&project, &["build"], "")), Some(1));
+    assert_eq!(status(&invariant_in(&project, &["check"], "")), Some(1));
+
+    fs::remove_dir_all(&directory).unwrap();
+}
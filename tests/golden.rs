@@ -0,0 +1,183 @@
+//! Snapshot tests that parse every `examples/*.inv` file and compare the
+//! printed tree against the `.ast` file next to it.
+//!
+//! Run with `INVARIANT_BLESS=1` to rewrite the snapshots instead.
+
+use invariant::ast::TreePrinter;
+use invariant::lexer::Lexer;
+use invariant::parser::Parser;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BLESS_VARIABLE: &str = "INVARIANT_BLESS";
+
+fn examples() -> Vec<PathBuf> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let mut examples = fs::read_dir(&directory)
+        .expect("the examples directory should exist")
+        .map(|entry| entry.expect("examples should be readable").path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "inv"))
+        .collect::<Vec<_>>();
+    examples.sort();
+
+    examples
+}
+
+/// Renders the tree of `source`, followed by every parse error.
+fn render(source: &str) -> String {
+    let (program, errors) = Parser::new(Lexer::new(source)).parse();
+    let mut rendered = TreePrinter::new(false).print_program(&program);
+    for error in errors {
+        let _ = writeln!(rendered, "error: {error}");
+    }
+
+    rendered
+}
+
+/// A line diff based on the longest common subsequence of both sides.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+
+    let mut common = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            let _ = writeln!(out, "  {}", expected[i]);
+            i += 1;
+            j += 1;
+        } else if i < expected.len() && (j == actual.len() || common[i + 1][j] >= common[i][j + 1])
+        {
+            let _ = writeln!(out, "- {}", expected[i]);
+            i += 1;
+        } else {
+            let _ = writeln!(out, "+ {}", actual[j]);
+            j += 1;
+        }
+    }
+
+    out
+}
+
+#[test]
+fn examples_match_their_snapshots() {
+    let bless = std::env::var_os(BLESS_VARIABLE).is_some();
+    let mut failures = Vec::new();
+
+    for example in examples() {
+        let source = fs::read_to_string(&example).expect("examples should be readable");
+        let actual = render(&source);
+
+        let snapshot = example.with_extension("ast");
+        let expected = fs::read_to_string(&snapshot).ok();
+        let normalized = expected
+            .as_ref()
+            .map(|expected| expected.replace("\r\n", "\n"));
+
+        if normalized.as_deref() == Some(actual.as_str()) {
+            continue;
+        }
+
+        if bless {
+            // keep the line endings of the existing snapshot, or of the
+            // example for new ones
+            let crlf = expected.unwrap_or(source).contains("\r\n");
+            let actual = if crlf {
+                actual.replace('\n', "\r\n")
+            } else {
+                actual
+            };
+            fs::write(&snapshot, actual).expect("snapshots should be writable");
+            continue;
+        }
+
+        match normalized {
+            Some(expected) => failures.push(format!(
+                "{} does not match:\n{}",
+                snapshot.display(),
+                diff(&expected, &actual)
+            )),
+            None => failures.push(format!("{} is missing", snapshot.display())),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{}\nrun with {BLESS_VARIABLE}=1 to update the snapshots",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn diff_marks_changed_lines() {
+    assert_eq!(
+        diff("a\nb\nc\n", "a\nx\nc\nd\n"),
+        "  a\n- b\n+ x\n  c\n+ d\n"
+    );
+}
+
+#[test]
+fn examples_survive_formatting() {
+    for example in examples() {
+        let source = fs::read_to_string(&example).expect("examples should be readable");
+        let formatted = invariant::format::format(&source)
+            .unwrap_or_else(|errors| panic!("{} does not parse: {errors:?}", example.display()));
+
+        assert_eq!(
+            render(&formatted),
+            render(&source),
+            "formatting changed the tree of {}",
+            example.display()
+        );
+        assert_eq!(
+            invariant::format::format(&formatted).ok().as_deref(),
+            Some(formatted.as_str()),
+            "formatting {} is not idempotent",
+            example.display()
+        );
+    }
+}
+
+#[test]
+fn examples_round_trip_through_the_syntax_tree() {
+    for example in examples() {
+        let source = fs::read_to_string(&example).expect("examples should be readable");
+
+        assert!(
+            invariant::syntax::parse(&source).to_string() == source,
+            "the syntax tree of {} does not reproduce it",
+            example.display()
+        );
+    }
+}
+
+#[test]
+fn examples_round_trip_through_json() {
+    for example in examples() {
+        let source = fs::read_to_string(&example).expect("examples should be readable");
+        let (program, _) = Parser::new(Lexer::new(&source)).parse();
+
+        let json = invariant::serialize::program_to_json(&program).to_string();
+        let decoded = invariant::serialize::program_from_json_str(&json)
+            .unwrap_or_else(|error| panic!("{} does not decode: {error}", example.display()));
+
+        assert!(
+            TreePrinter::new(true).print_program(&decoded)
+                == TreePrinter::new(true).print_program(&program),
+            "{} changed on its way through JSON",
+            example.display()
+        );
+    }
+}